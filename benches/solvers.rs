@@ -0,0 +1,59 @@
+/*
+    Backtracking vs SAT vs DLX (and the SAT encodings against each other) on the bundled puzzle set.
+    Run with: cargo bench --features bench
+*/
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use sudoku::sudoku::{ClueEncoding, ConstraintSet, Encoding, PuzzleRecord, parse_csv, solve_backtracking, solve_dlx, solve_sat, solve_sat_encoded, solve_sat_report};
+
+fn puzzles() -> Vec<PuzzleRecord> {
+    parse_csv(include_str!("puzzles.csv")).expect("Invalid bundled puzzle set")
+}
+
+fn bench_solvers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("solvers");
+    group.sample_size(10); // Backtracking on the extreme puzzles takes a while
+
+    for (i, record) in puzzles().iter().enumerate() {
+        let label = format!("{}-{}", i + 1, record.difficulty);
+
+        group.bench_with_input(BenchmarkId::new("backtracking", &label), &record.puzzle, |b, puzzle| {
+            b.iter(|| solve_backtracking(&mut puzzle.clone()))
+        });
+
+        group.bench_with_input(BenchmarkId::new("sat", &label), &record.puzzle, |b, puzzle| {
+            b.iter(|| solve_sat(&mut puzzle.clone()))
+        });
+
+        group.bench_with_input(BenchmarkId::new("dlx", &label), &record.puzzle, |b, puzzle| {
+            b.iter(|| solve_dlx(&mut puzzle.clone()))
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_encodings(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encodings");
+
+    for (i, record) in puzzles().iter().enumerate() {
+        let label = format!("{}-{}", i + 1, record.difficulty);
+
+        for encoding in Encoding::ALL {
+            group.bench_with_input(BenchmarkId::new(encoding.to_string(), &label), &record.puzzle, |b, puzzle| {
+                b.iter(|| solve_sat_encoded(&mut puzzle.clone(), &ConstraintSet::default(), encoding))
+            });
+        }
+
+        // Does forbidding the other values of clue cells up front help propagation?
+        for clues in ClueEncoding::ALL {
+            group.bench_with_input(BenchmarkId::new(format!("{}-clues", clues), &label), &record.puzzle, |b, puzzle| {
+                b.iter(|| solve_sat_report(&mut puzzle.clone(), &ConstraintSet::default(), Encoding::Minimal, clues))
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_solvers, bench_encodings);
+criterion_main!(benches);
@@ -0,0 +1,62 @@
+use std::time::{Duration, Instant};
+use rand::{Rng, seq::SliceRandom};
+
+// Time for a whole generated puzzle to appear, whatever its clue count.
+pub const DEAL_DURATION: Duration = Duration::from_millis(500);
+
+// Clues of a freshly generated puzzle appearing one by one, in random order. The board itself is already complete.
+pub struct Deal {
+    order: Vec<(usize, usize)>,
+    start: Instant,
+}
+
+impl Deal {
+    pub fn new(matrix: &[Vec<i8>], random: &mut impl Rng) -> Self {
+        Self {order: deal_order(matrix, random), start: Instant::now()}
+    }
+
+    // Cells not dealt yet, to be drawn blank.
+    pub fn hidden(&self) -> &[(usize, usize)] {
+        &self.order[revealed_count(self.order.len(), self.start.elapsed(), DEAL_DURATION)..]
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.hidden().is_empty()
+    }
+}
+
+// Filled cells of `matrix`, shuffled: the order in which they are dealt.
+pub fn deal_order(matrix: &[Vec<i8>], random: &mut impl Rng) -> Vec<(usize, usize)> {
+    let mut order: Vec<(usize, usize)> = matrix.iter()
+        .enumerate()
+        .flat_map(|(row, values)| values.iter().enumerate().filter(|&(_, &value)| value != 0).map(move |(col, _)| (row, col)))
+        .collect();
+
+    order.shuffle(random);
+    order
+}
+
+// Cells shown after `elapsed`, evenly spread over `duration`: all of them once it is over.
+pub fn revealed_count(total: usize, elapsed: Duration, duration: Duration) -> usize {
+    if elapsed >= duration {return total}
+
+    (total as f64 * elapsed.as_secs_f64() / duration.as_secs_f64()) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    #[test]
+    fn clues_are_dealt_evenly_and_once() {
+        let matrix = vec![vec![1, 0, 0, 4], vec![0, 4, 1, 0], vec![2, 0, 4, 0], vec![0, 3, 0, 1]];
+        let mut order = deal_order(&matrix, &mut StdRng::seed_from_u64(2));
+
+        order.sort();
+        assert_eq!(order, [(0, 0), (0, 3), (1, 1), (1, 2), (2, 0), (2, 2), (3, 1), (3, 3)]);
+
+        let at = |millis| revealed_count(8, Duration::from_millis(millis), DEAL_DURATION);
+        assert_eq!([at(0), at(125), at(250), at(499), at(500), at(2000)], [0, 2, 4, 7, 8, 8]);
+    }
+}
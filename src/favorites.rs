@@ -0,0 +1,70 @@
+use std::{fs, io, path::PathBuf};
+use serde::{Deserialize, Serialize};
+
+/*
+    Starred puzzles, kept in a JSON file inside the OS config directory
+    (e.g. ~/.config/sudoku-sat/favorites.json on Linux).
+*/
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Favorite {
+    pub name: Option<String>,
+    pub puzzle: String, // As produced by sudoku::export_puzzle_to_string
+}
+
+impl Favorite {
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.puzzle)
+    }
+}
+
+pub fn favorites_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("sudoku-sat").join("favorites.json"))
+}
+
+pub fn to_json(favorites: &[Favorite]) -> String {
+    serde_json::to_string_pretty(favorites).expect("Favorites serialization err")
+}
+
+pub fn from_json(json: &str) -> Result<Vec<Favorite>, serde_json::Error> {
+    serde_json::from_str(json)
+}
+
+// A missing or corrupt file is not an error for the user: just start with no favorites.
+pub fn load_favorites() -> Vec<Favorite> {
+    let Some(path) = favorites_path() else {return Vec::new()};
+
+    match fs::read_to_string(&path) {
+        Ok(json) => from_json(&json).unwrap_or_else(|err| {
+            println!("Ignoring corrupt favorites file {}: {}", path.display(), err);
+            Vec::new()
+        }),
+        Err(_) => Vec::new(),
+    }
+}
+
+pub fn save_favorites(favorites: &[Favorite]) -> io::Result<()> {
+    let path = favorites_path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No config directory"))?;
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    fs::write(path, to_json(favorites))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn favorites_round_trip_through_json() {
+        let favorites = vec![
+            Favorite {name: Some("Morning".to_string()), puzzle: "1204301221004301".to_string()},
+            Favorite {name: None, puzzle: "0034341200434321".to_string()},
+        ];
+
+        assert_eq!(from_json(&to_json(&favorites)).unwrap(), favorites);
+        assert_eq!(favorites[1].label(), "0034341200434321");
+        assert!(from_json("[{\"puzzle\": 5}").is_err());
+    }
+}
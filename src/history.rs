@@ -0,0 +1,174 @@
+use std::collections::{HashMap, VecDeque};
+use serde::{Deserialize, Serialize};
+
+use sudoku::sudoku::{Algorithm, SolvedBy};
+
+// Oldest solves are dropped past this, the chart would be unreadable anyway.
+pub const MAX_HISTORY: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SolveRecord {
+    pub seconds: f64, // Infinite when the puzzle was unsolvable
+    pub algorithm: Algorithm,
+    pub solved_by: Option<SolvedBy>,
+}
+
+// Solve times since the last reset, oldest first.
+#[derive(Clone, Debug, Default)]
+pub struct SolveHistory {
+    records: VecDeque<SolveRecord>,
+}
+
+impl SolveHistory {
+    pub fn push(&mut self, record: SolveRecord) {
+        if self.records.len() == MAX_HISTORY {
+            self.records.pop_front();
+        }
+        self.records.push_back(record);
+    }
+
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn records(&self) -> impl Iterator<Item = &SolveRecord> {
+        self.records.iter()
+    }
+}
+
+// Fastest solve time for each board side (9 for 9x9), kept between sessions.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BestTimes {
+    times: HashMap<usize, f64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BestOutcome {
+    NewBest,
+    Previous(f64), // Not beaten: the best time that still stands
+}
+
+impl BestTimes {
+    // Only finite times count, and the stored one only changes on a strict improvement.
+    pub fn record(&mut self, size: usize, seconds: f64) -> Option<BestOutcome> {
+        if !seconds.is_finite() {return None}
+
+        match self.times.get(&size) {
+            Some(&best) if best <= seconds => Some(BestOutcome::Previous(best)),
+            _ => {
+                self.times.insert(size, seconds);
+                Some(BestOutcome::NewBest)
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.times.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.times.is_empty()
+    }
+}
+
+// The player's own placements, oldest first, to take back one at a time. Solving and generating never add to it.
+#[derive(Clone, Debug, Default)]
+pub struct PlayerMoves {
+    moves: Vec<(usize, usize, i8)>, // (row, col, previous value)
+}
+
+impl PlayerMoves {
+    // Consecutive edits of the same cell count as one move, which takes the cell back to its value before the first.
+    pub fn record(&mut self, (row, col): (usize, usize), prev_value: i8) {
+        if let Some(&(last_row, last_col, _)) = self.moves.last()
+            && (last_row, last_col) == (row, col) {
+            return;
+        }
+        self.moves.push((row, col, prev_value));
+    }
+
+    // Undoes the last move in `matrix`, returning its cell and the value put back.
+    pub fn take_back(&mut self, matrix: &mut [Vec<i8>]) -> Option<((usize, usize), i8)> {
+        let (row, col, prev_value) = self.moves.pop()?;
+        matrix[row][col] = prev_value;
+
+        Some(((row, col), prev_value))
+    }
+
+    pub fn clear(&mut self) {
+        self.moves.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn taking_back_moves_restores_each_cell() {
+        let start = vec![vec![1, 0, 0, 0], vec![0; 4], vec![0; 4], vec![0; 4]];
+        let mut matrix = start.clone();
+        let mut moves = PlayerMoves::default();
+        let mut states = Vec::new();
+
+        for (pos, value) in [((0, 1), 2), ((1, 1), 3), ((1, 1), 4), ((0, 0), 0)] {
+            // Repeated edits of a cell merge, so only the state before the first one is a take-back point.
+            if states.last().is_none_or(|(last, _)| *last != pos) {
+                states.push((pos, matrix.clone()));
+            }
+            moves.record(pos, matrix[pos.0][pos.1]);
+            matrix[pos.0][pos.1] = value;
+        }
+
+        while let Some((pos, before)) = states.pop() {
+            assert_eq!(moves.take_back(&mut matrix), Some((pos, before[pos.0][pos.1])));
+            assert_eq!(matrix, before);
+        }
+
+        assert_eq!(matrix, start);
+        assert!(moves.is_empty() && moves.take_back(&mut matrix).is_none());
+    }
+
+    #[test]
+    fn solve_history_keeps_the_latest_records() {
+        let mut history = SolveHistory::default();
+        assert!(history.is_empty());
+
+        for i in 0..MAX_HISTORY + 5 {
+            history.push(SolveRecord {seconds: i as f64, algorithm: Algorithm::Sat, solved_by: Some(SolvedBy::Sat)});
+        }
+
+        let seconds: Vec<f64> = history.records().map(|record| record.seconds).collect();
+        assert_eq!(seconds.len(), MAX_HISTORY);
+        assert_eq!((seconds[0], seconds[MAX_HISTORY - 1]), (5., (MAX_HISTORY + 4) as f64));
+
+        history.clear();
+        assert!(history.is_empty());
+    }
+
+    #[test]
+    fn best_times_only_improve() {
+        let mut best = BestTimes::default();
+
+        assert_eq!(best.record(9, 2.), Some(BestOutcome::NewBest));
+        assert_eq!(best.record(9, 3.), Some(BestOutcome::Previous(2.)));
+        assert_eq!(best.record(9, 2.), Some(BestOutcome::Previous(2.)));
+        assert_eq!(best.record(9, 1.5), Some(BestOutcome::NewBest));
+        assert_eq!(best.record(9, 1.8), Some(BestOutcome::Previous(1.5)));
+
+        // Sizes are tracked apart, unsolvable puzzles don't count
+        assert_eq!(best.record(4, 5.), Some(BestOutcome::NewBest));
+        assert_eq!(best.record(9, f64::INFINITY), None);
+
+        best.clear();
+        assert!(best.is_empty());
+    }
+}
@@ -0,0 +1,17 @@
+//! Sudoku solving through a reduction to SAT (plus backtracking and human-style techniques).
+//!
+//! The GUI binary is built on top of this library, which can also be used on its own:
+//!
+//! ```
+//! use sudoku::sudoku::{parse_puzzle, solve_sat, is_matrix_valid};
+//!
+//! let mut puzzle = parse_puzzle(
+//!     "530070000600195000098000060800060003400803001700020006060000280000419005000080079"
+//! ).unwrap();
+//!
+//! assert!(solve_sat(&mut puzzle));
+//! assert!(is_matrix_valid(&puzzle).is_empty());
+//! assert_eq!(puzzle[0], vec![5, 3, 4, 6, 7, 8, 9, 1, 2]);
+//! ```
+
+pub mod sudoku;
@@ -1,23 +1,39 @@
-mod sudoku;
+mod deal;
+mod favorites;
+mod history;
+mod replay;
+mod worker;
 
-use std::{sync::mpsc::{self, Receiver}};
+use std::{collections::BTreeMap, sync::{atomic::AtomicBool, mpsc::{self, Receiver, TryRecvError}}, time::{Duration, Instant}};
 use eframe::{run_native, App, CreationContext, NativeOptions};
+use sudoku::sudoku;
+
+// Share QR code, or why it couldn't be made, with the board it encodes.
+type SharedQr = (Vec<Vec<i8>>, Result<sudoku::qr::QrCode, String>);
+// Whether a board can still be completed, with the board and rules it was checked on.
+type Completable = (Vec<Vec<i8>>, sudoku::ConstraintSet, bool);
+// Size of each encoding's formula, with the board, rules and clue encoding it was built from.
+type EncodingStats = (Vec<Vec<i8>>, sudoku::ConstraintSet, sudoku::ClueEncoding, Vec<sudoku::FormulaStats>);
 
 fn main() {
 
-    let icon = include_bytes!("../assets/icon.png");
-    let image = image::load_from_memory(icon).expect("Failed to open icon path").to_rgba8();
-    let (icon_width, icon_height) = image.dimensions();
+    // Headless mode: `sudoku --serve <port>` answers HTTP requests instead of opening the window.
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(i) = args.iter().position(|arg| arg == "--serve") {
+        run_server(args.get(i + 1).map(String::as_str));
+        return;
+    }
 
-    let options = NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_icon(egui::IconData {
-                rgba: image.into_raw(), 
-                width: icon_width, 
-                height: icon_height,
-            })
-            .with_min_inner_size([400.0, 300.0]), // Minimum window size
+    let mut viewport = egui::ViewportBuilder::default()
+        .with_min_inner_size([400.0, 300.0]); // Minimum window size
 
+    // A broken icon is not worth refusing to start: fall back to the platform default.
+    if let Some(icon) = load_icon(include_bytes!("../assets/icon.png")) {
+        viewport = viewport.with_icon(icon);
+    }
+
+    let options = NativeOptions {
+        viewport,
 
         ..Default::default()
     };
@@ -31,48 +47,873 @@ fn main() {
     .unwrap();
 }
 
+#[cfg(feature = "serve")]
+fn run_server(port: Option<&str>) {
+    let Some(port) = port.and_then(|port| port.parse::<u16>().ok()) else {
+        println!("Usage: sudoku --serve <port>");
+        return;
+    };
+
+    if let Err(err) = sudoku::server::serve(port) {
+        println!("Server error: {}", err);
+    }
+}
+
+#[cfg(not(feature = "serve"))]
+fn run_server(_port: Option<&str>) {
+    println!("This build has no server mode, rebuild with: cargo build --features serve");
+}
+
+fn load_icon(bytes: &[u8]) -> Option<egui::IconData> {
+    match image::load_from_memory(bytes) {
+        Ok(image) => {
+            let image = image.to_rgba8();
+            let (width, height) = image.dimensions();
+            Some(egui::IconData {rgba: image.into_raw(), width, height})
+        }
+        Err(err) => {
+            println!("Warning: could not load the app icon ({}), using the default one.", err);
+            None
+        }
+    }
+}
+
+const DEFAULT_ALGORITHM_KEY: &str = "default_algorithm";
+const BEST_TIMES_KEY: &str = "best_times";
+const PANEL_ON_LEFT_KEY: &str = "panel_on_left";
+const MAX_MATRIX_SIZE_KEY: &str = "max_matrix_size";
+const EXPORT_DIR_KEY: &str = "export_dir";
+const MIN_GRID_SCALE: f32 = 0.5;
+const MAX_GRID_SCALE: f32 = 2.;
+const PLAYER_COLOR: egui::Color32 = egui::Color32::from_rgb(70, 130, 230);
+const SOLVER_COLOR: egui::Color32 = egui::Color32::from_rgb(140, 140, 140);
+const SUGGESTED_COLOR: egui::Color32 = egui::Color32::from_rgb(0, 160, 120); // Teal, readable on both themes
+const SUSPICIOUS_COLOR: egui::Color32 = egui::Color32::from_rgb(200, 60, 170);
+const MAX_BROWSED_SOLUTIONS: usize = 50;
+const DEFAULT_REPLAY_PATH: &str = "sudoku.replay";
+const FAST_FORWARD_STEPS: usize = 500; // Backtracking steps per frame when the animation delay is 0
+const DEAD_END_CHECK_MAX_SIZE: usize = 16; // A 25x25 check would hold up the worker's queue for seconds
+const DEAD_END_CHECK_DELAY: Duration = Duration::from_millis(300); // Pause in editing before the board is checked
+
+// Column header for the grid, spreadsheet style: A to Z, then AA, AB... so any board side gets distinct labels.
+fn column_label(col: usize) -> String {
+    let mut label = Vec::new();
+    let mut rest = col + 1;
+
+    while rest > 0 {
+        rest -= 1;
+        label.push(b'A' + (rest % 26) as u8);
+        rest /= 26;
+    }
+
+    label.iter().rev().map(|&byte| char::from(byte)).collect()
+}
+
+// Documents folder (or home) rather than wherever the app was started from; the working directory as a last resort.
+fn default_export_dir() -> String {
+    dirs::document_dir().or_else(dirs::home_dir).map_or_else(|| ".".to_string(), |dir| dir.display().to_string())
+}
+
+// Presenting, cells only report hovering: no click selects, edits or opens a popup.
+fn cell_sense(presenting: bool) -> egui::Sense {
+    if presenting {egui::Sense::hover()} else {egui::Sense::click()}
+}
+
+// Background of a jigsaw region: hues spread around the wheel so neighbouring regions stand apart.
+fn region_color(region: usize, regions: usize, dark: bool) -> egui::Color32 {
+    let hue = region as f32 / regions.max(1) as f32;
+    egui::ecolor::Hsva::new(hue, 0.35, if dark {0.35} else {0.95}, 1.).into()
+}
+
+// Animation steps due this frame: one per elapsed delay, or a whole batch when there is no delay.
+fn steps_per_tick(delay_ms: u64, elapsed: Duration) -> usize {
+    if delay_ms == 0 {FAST_FORWARD_STEPS} else {(elapsed.as_millis() / delay_ms as u128) as usize}
+}
+
+/*
+    The result of a background job once it arrived, emptying `slot`. A job that panicked never sends one:
+    its channel disconnects instead, which also empties `slot` and leaves a message in `error`.
+*/
+fn poll<T>(slot: &mut Option<Receiver<T>>, error: &mut Option<String>) -> Option<T> {
+    let result = match slot.as_ref()?.try_recv() {
+        Err(TryRecvError::Empty) => return None,
+        result => result,
+    };
+
+    *slot = None;
+    if result.is_err() {
+        *error = Some("\u{26A0} A background computation failed, see the console for details.".to_string());
+    }
+
+    result.ok()
+}
+
+// Paging through the solutions of an ambiguous puzzle.
+struct SolutionBrowser {
+    puzzle: Vec<Vec<i8>>, // Restored when browsing ends
+    solutions: Vec<Vec<Vec<i8>>>,
+    index: usize,
+}
+
 struct MatrixApp {
     matrix_size: usize,
+    max_matrix_size: usize, // Cap on matrix_size (and on solvable boards), lowered on machines short on memory
     matrix: Vec<Vec<i8>>, // Matrix of 8-bit integers
     ui_scale: f32,
+    grid_scale: f32, // Zoom of the grid alone, on top of ui_scale
+    grid_size: egui::Vec2, // As drawn on the previous frame, to center the grid
     dark_mode: bool, // Track light/dark mode
+    panel_on_left: bool, // Side of the settings/operations panel
     invalid_poss: Vec<(usize, usize)>,
+    redundant_clues: Vec<(usize, usize)>, // Clues removable without losing uniqueness
+    suspicious_cells: Vec<(usize, usize)>, // Filled cells that together make the puzzle unsolvable
+    suspicious_msg: Option<String>,
+    previous_solution: Option<Vec<Vec<i8>>>,
+    changed_cells: Vec<(usize, usize)>, // Cells differing from the previous solution
+    pending_solve: bool, // The awaited matrix is a solver result (not a generated puzzle)
+    initial_matrix: Option<Vec<Vec<i8>>>, // The puzzle as generated/loaded, for "Revert to Puzzle"
     show_correctness: bool,
+    show_coordinates: bool, // Column letters and row numbers around the grid
+    show_about: bool,
+    blank_style: sudoku::BlankStyle, // Blank cells on the grid and in copied puzzle strings
+    clue_encoding: sudoku::ClueEncoding, // Used by SAT solves, and shown in the encoding comparison
     solution_time: f64,
+    solved_by: Option<sudoku::SolvedBy>,
+    fallback_steps: usize, // Backtracking moves before Auto switches to SAT
+    player_moves: history::PlayerMoves, // Manual placements, for Take Back Move
+    origins: Vec<Vec<sudoku::CellOrigin>>, // Provenance of every cell, same shape as the matrix
+    suggested_cells: Vec<(usize, usize)>, // Cells filled by "Suggest a Move", until taken back or edited
+    show_easy_moves: bool, // Highlight the last empty cell of each row, column and block
+    favorites: Vec<favorites::Favorite>,
+    selected_favorite: Option<usize>,
+    favorite_name: String,
+    sat_display_limit: usize, // Max clauses rendered in the SAT reduction popup
+    sat_summary: bool, // Clause counts per category instead of the raw CNF
+    sat_summary_text: Option<(Vec<Vec<i8>>, sudoku::ConstraintSet, String)>, // Last summary, with the board and rules it counts
+    cnf_export_msg: Option<String>,
+    validation_msg: Option<String>, // Outcome of the clue check done by "Validate & Solve"
+    pdf_options: sudoku::pdf::PdfOptions,
+    pdf_export_msg: Option<String>,
+    swap_pair: (i8, i8),
+    image_path: String,
+    bulk_text: String, // Rows pasted in the bulk edit popup
+    bulk_msg: Option<String>,
+    region_map: String, // Jigsaw region map being edited
+    regions_msg: Option<String>,
+    import_msg: Option<String>,
+    qr: Option<SharedQr>,
+    qr_msg: Option<String>,
+    reference: Option<Vec<Vec<i8>>>, // Known solution of the loaded puzzle (from a CSV), to check solver output against
+    reference_check: Option<sudoku::ReferenceCheck>,
+    replay_path: String,
+    export_dir: String, // Folder for exported files (CNF, graph, PDF, QR, replays), persisted between sessions
+    replay_msg: Option<String>,
+    puzzle_seed: Option<u64>, // Seed of the current puzzle when it was generated from one, recorded in replays
+    practice_technique: sudoku::Technique, // Technique the "Practice" puzzles require
+    practice_msg: Option<String>,
+    target_clues: usize, // For "Generate Unique Puzzle"
+    generation_msg: Option<String>,
+    default_algorithm: sudoku::Algorithm, // Used by the plain "Solve" button, persisted between sessions
+    constraints: sudoku::ConstraintSet, // Variant rules enabled in the settings
+    pencil_marks: BTreeMap<(usize, usize), Vec<i8>>, // Values the player kept for a cell, set from the edit popup
+    respect_pencil_marks: bool, // Solve as if the pencil marks were rules, to test hypotheses
+    hovered_cell: Option<(usize, usize)>,
+    selected_cell: Option<(usize, usize)>, // Left-clicked cell, target of the keypad and of typed digits
+    auto_advance: bool, // Move the selection to the next cell after an entry
+    deal_animation: bool, // Reveal generated puzzles clue by clue
+    presentation_mode: bool, // Read-only grid alone, for screenshots and slides
+    present_solution: bool, // Show the solved board in presentation mode
+    presentation_board: Option<Vec<Vec<i8>>>, // Solution shown while presenting, when asked for and found
+    deal: Option<deal::Deal>,
+    warn_dead_ends: bool, // Check after every change that the board can still be completed
+    completable: Option<Completable>, // Last dead-end check
+    dead_end_settle: Option<(Vec<Vec<i8>>, Instant)>, // Board waiting for its dead-end check, and since when it is unchanged
+    estimate: Option<(Vec<Vec<i8>>, sudoku::Estimate)>, // Backtracking preview, with the board it was made on
+    encoding_stats: Option<EncodingStats>, // Built formulas' sizes, with what they were built from
+    advance_wraps: bool, // After the last cell, go back to the first one instead of stopping
+    browser: Option<SolutionBrowser>,
+    solve_history: history::SolveHistory,
+    best_times: history::BestTimes, // Per board size, persisted between sessions
+    best_outcome: Option<history::BestOutcome>, // How the last solve compares to the best time
+    solving_algorithm: Option<sudoku::Algorithm>, // Set while a solve runs, to record it once every result is in
+
+    // Step-through backtracking animation
+    stepper: Option<sudoku::BacktrackStepper>,
+    step_delay_ms: u64,
+    animation_paused: bool,
+    last_step: Instant,
 
     // Thread management
     rx_matrix: Option<Receiver<Vec<Vec<i8>>>>,
     rx_time: Option<Receiver<f64>>,
+    rx_solved_by: Option<Receiver<Result<sudoku::SolvedBy, sudoku::SolveStopped>>>,
+    rx_redundant: Option<Receiver<Vec<(usize, usize)>>>,
+    rx_suspicious: Option<Receiver<Vec<(usize, usize)>>>,
+    rx_solutions: Option<Receiver<Vec<Vec<Vec<i8>>>>>,
+    rx_practice: Option<Receiver<Result<Vec<Vec<i8>>, sudoku::GenerationError>>>,
+    rx_generation: Option<Receiver<sudoku::GenerationResult>>,
+    rx_presentation: Option<Receiver<Option<Vec<Vec<i8>>>>>,
+    rx_region: Option<Receiver<Option<Vec<Vec<i8>>>>>, // Board with one more block filled by "Solve Block"
+    rx_completable: Option<Receiver<Completable>>, // Not part of is_computing: the check never blocks the UI
+    job_error: Option<String>, // Why the last background job gave no result
+    worker: worker::Worker, // Runs the background computations, one at a time
 }
 
 impl MatrixApp {
-    fn new(_: &CreationContext<'_>) -> Self {
+    fn new(cc: &CreationContext<'_>) -> Self {
+        Self::with_storage(cc.storage)
+    }
+
+    // Settings come from `storage` when there is one (the first launch has none), defaults otherwise.
+    fn with_storage(storage: Option<&dyn eframe::Storage>) -> Self {
         Self {
             matrix_size: 3,
+            max_matrix_size: storage.and_then(|storage| eframe::get_value(storage, MAX_MATRIX_SIZE_KEY)).unwrap_or(sudoku::DEFAULT_MAX_SIZE.isqrt()),
             matrix: vec![vec![0; 9]; 9],
             ui_scale: 1.,
+            grid_scale: 1.,
+            grid_size: egui::Vec2::ZERO,
             dark_mode: true,
+            panel_on_left: storage.and_then(|storage| eframe::get_value(storage, PANEL_ON_LEFT_KEY)).unwrap_or(false),
             invalid_poss: Vec::new(),
+            redundant_clues: Vec::new(),
+            suspicious_cells: Vec::new(),
+            suspicious_msg: None,
+            previous_solution: None,
+            changed_cells: Vec::new(),
+            pending_solve: false,
+            initial_matrix: None,
             show_correctness: false,
+            show_coordinates: false,
+            show_about: false,
+            blank_style: sudoku::BlankStyle::Empty,
+            clue_encoding: sudoku::ClueEncoding::default(),
             solution_time: f64::NAN,
+            solved_by: None,
+            fallback_steps: sudoku::DEFAULT_FALLBACK_STEPS,
+            player_moves: history::PlayerMoves::default(),
+            origins: vec![vec![sudoku::CellOrigin::Empty; 9]; 9],
+            suggested_cells: Vec::new(),
+            show_easy_moves: false,
+            favorites: favorites::load_favorites(),
+            selected_favorite: None,
+            favorite_name: String::new(),
+            sat_display_limit: 10_000,
+            sat_summary: false,
+            sat_summary_text: None,
+            cnf_export_msg: None,
+            validation_msg: None,
+            pdf_options: sudoku::pdf::PdfOptions::default(),
+            pdf_export_msg: None,
+            swap_pair: (1, 2),
+            image_path: String::new(),
+            bulk_text: String::new(),
+            bulk_msg: None,
+            region_map: String::new(),
+            regions_msg: None,
+            import_msg: None,
+            qr: None,
+            qr_msg: None,
+            reference: None,
+            reference_check: None,
+            replay_path: String::new(),
+            export_dir: storage.and_then(|storage| eframe::get_value(storage, EXPORT_DIR_KEY)).unwrap_or_else(default_export_dir),
+            replay_msg: None,
+            puzzle_seed: None,
+            practice_technique: sudoku::Technique::HiddenSingle,
+            practice_msg: None,
+            target_clues: 30,
+            generation_msg: None,
+            hovered_cell: None,
+            selected_cell: None,
+            auto_advance: false,
+            deal_animation: true,
+            presentation_mode: false,
+            present_solution: false,
+            presentation_board: None,
+            deal: None,
+            warn_dead_ends: true,
+            completable: None,
+            dead_end_settle: None,
+            estimate: None,
+            encoding_stats: None,
+            advance_wraps: false,
+            browser: None,
+            solve_history: history::SolveHistory::default(),
+            best_times: storage.and_then(|storage| eframe::get_value(storage, BEST_TIMES_KEY)).unwrap_or_default(),
+            best_outcome: None,
+            solving_algorithm: None,
+            constraints: sudoku::ConstraintSet::default(),
+            pencil_marks: BTreeMap::new(),
+            respect_pencil_marks: false,
+            stepper: None,
+            step_delay_ms: 50,
+            animation_paused: false,
+            last_step: Instant::now(),
+            default_algorithm: storage.and_then(|storage| eframe::get_value(storage, DEFAULT_ALGORITHM_KEY)).unwrap_or_default(),
             rx_matrix: None,
-            rx_time: None
+            rx_time: None,
+            rx_solved_by: None,
+            rx_redundant: None,
+            rx_suspicious: None,
+            rx_solutions: None,
+            rx_practice: None,
+            rx_generation: None,
+            rx_presentation: None,
+            rx_region: None,
+            rx_completable: None,
+            job_error: None,
+            worker: worker::Worker::new(),
         }
     }
 
     fn update_matrix(&mut self) {
         self.matrix = vec![vec![0; self.matrix_size.pow(2)]; self.matrix_size.pow(2)];
+        self.origins = vec![vec![sudoku::CellOrigin::Empty; self.matrix_size.pow(2)]; self.matrix_size.pow(2)];
         self.invalid_poss.clear();
+        self.redundant_clues.clear();
+        self.suspicious_cells.clear();
+        self.suspicious_msg = None;
+        self.changed_cells.clear();
         self.show_correctness = false;
         self.solution_time = f64::NAN;
+        self.solved_by = None;
+        self.player_moves.clear();
+        self.suggested_cells.clear();
+        self.initial_matrix = None;
+        self.browser = None;
+        self.selected_cell = None;
+        self.pencil_marks.clear();
+        self.puzzle_seed = None;
+        self.validation_msg = None;
+        self.deal = None;
+        self.reference = None;
+        self.reference_check = None;
+
+        // Regions only fit the board size they were drawn for.
+        if self.constraints.regions.as_ref().is_some_and(|regions| regions.size() != self.matrix.len()) {
+            self.constraints.regions = None;
+            self.regions_msg = None;
+        }
+    }
+
+    // A background computation or an animation owns the grid.
+    fn is_busy(&self) -> bool {
+        self.is_computing() || self.stepper.is_some()
+    }
+
+    fn is_computing(&self) -> bool {
+        self.rx_matrix.is_some() || self.rx_redundant.is_some() || self.rx_suspicious.is_some() || self.rx_solutions.is_some() || self.rx_practice.is_some() || self.rx_generation.is_some() || self.rx_presentation.is_some() || self.rx_region.is_some()
+    }
+
+    // Digits typed while a cell is selected go in that cell (letters from A for 10 up on big boards), Backspace/Delete clear it.
+    fn handle_keyboard_entry(&mut self, ctx: &egui::Context) {
+        let Some((row, col)) = self.selected_cell else {return};
+
+        // Text fields (and the zoom shortcuts) get the keys first.
+        if self.is_busy() || self.presentation_mode || ctx.wants_keyboard_input() || ctx.input(|i| i.modifiers.command) {return}
+
+        let size = self.matrix.len();
+
+        let entered = ctx.input(|i| {
+            if i.key_pressed(egui::Key::Backspace) || i.key_pressed(egui::Key::Delete) {
+                return Some(0);
+            }
+            // Typed text rather than keys: the number row, the keypad and letters alike, whatever the keyboard layout.
+            i.events.iter().find_map(|event| match event {
+                egui::Event::Text(text) => text.chars().next().and_then(|ch| sudoku::value_from_char(ch, size)),
+                _ => None,
+            })
+        });
+
+        let Some(value) = entered else {return};
+        self.set_cell(row, col, value);
+
+        if self.auto_advance && value != 0 {
+            // Stopping at the end keeps the last cell selected.
+            if let Some(next) = sudoku::next_cell(size, (row, col), self.advance_wraps) {
+                self.selected_cell = Some(next);
+            }
+        }
+    }
+
+    // Queues a background job; a failure reported by an earlier one no longer applies.
+    fn submit(&mut self, job: impl FnOnce(&AtomicBool) + Send + 'static) {
+        self.job_error = None;
+        self.worker.submit(job);
+    }
+
+    // Hides the panel and makes the grid read-only, with the board solved in the background first if present_solution is set.
+    fn toggle_presentation(&mut self) {
+        self.presentation_mode = !self.presentation_mode;
+        self.presentation_board = None;
+        self.rx_presentation = None; // A solve still running when leaving is abandoned
+
+        if self.presentation_mode && self.present_solution {
+            let (tx, rx) = mpsc::channel::<Option<Vec<Vec<i8>>>>();
+            let mut solution = self.matrix.clone();
+            let constraints = self.solving_constraints();
+            let clues = self.clue_encoding;
+
+            self.submit(move |_| {
+                let solved = sudoku::solve_sat_clues(&mut solution, &constraints, clues);
+                tx.send(solved.then_some(solution)).unwrap();
+            });
+
+            self.rx_presentation = Some(rx);
+        }
+    }
+
+    /*
+        Collects the results of background jobs. Runs first thing every frame, outside any panel,
+        so results keep coming while the side panel is hidden (e.g. when presenting).
+    */
+    fn poll_results(&mut self, ctx: &egui::Context) {
+        if let Some(result) = poll(&mut self.rx_generation, &mut self.job_error) {
+            if let Some(missed) = result.missed {
+                self.generation_msg = Some(format!("\u{26A0} Asked for {} clues, got {}: {}.", result.target, sudoku::clue_count(&result.puzzle), missed));
+            }
+
+            // Loaded like any generated puzzle, target met or not.
+            let (tx, rx) = mpsc::channel::<Vec<Vec<i8>>>();
+            tx.send(result.puzzle).unwrap();
+            self.rx_matrix = Some(rx);
+        }
+
+        if let Some(result) = poll(&mut self.rx_practice, &mut self.job_error) {
+            match result {
+                Ok(puzzle) => {
+                    // Loaded like any generated puzzle.
+                    let (tx, rx) = mpsc::channel::<Vec<Vec<i8>>>();
+                    tx.send(puzzle).unwrap();
+                    self.rx_matrix = Some(rx);
+                }
+                Err(err) => self.practice_msg = Some(format!("Practice: {}.", err)),
+            }
+        }
+
+        if let Some(cells) = poll(&mut self.rx_redundant, &mut self.job_error) {
+            println!("{} redundant clues.", cells.len());
+            self.redundant_clues = cells;
+        }
+
+        if let Some(cells) = poll(&mut self.rx_suspicious, &mut self.job_error) {
+            self.suspicious_msg = Some(if cells.is_empty() {
+                "Nothing suspicious: the puzzle can still be solved.".to_string()
+            } else {
+                format!("These {} cells can't all be right.", cells.len())
+            });
+            self.suspicious_cells = cells;
+        }
+
+        if let Some(solutions) = poll(&mut self.rx_solutions, &mut self.job_error) {
+            println!("{} solutions to browse.", solutions.len());
+
+            if let Some(first) = solutions.first() {
+                sudoku::update_origins(&mut self.origins, &self.matrix, first, sudoku::CellOrigin::Solver);
+                self.browser = Some(SolutionBrowser {puzzle: std::mem::replace(&mut self.matrix, first.clone()), solutions, index: 0});
+            } else {
+                self.solution_time = f64::INFINITY; // Shows the unsolvable message
+            }
+        }
+
+        // Check completition (if there is any) with non-blocking receive
+        if let Some(new_matrix) = poll(&mut self.rx_matrix, &mut self.job_error) {
+
+            let solved = std::mem::take(&mut self.pending_solve);
+
+            // Compare against the last solution, as long as this is a (complete) solution of the same size.
+            if solved && sudoku::is_matrix_valid_with(&new_matrix, &self.constraints).is_empty() {
+                self.changed_cells = match &self.previous_solution {
+                    Some(prev) if prev.len() == new_matrix.len() => sudoku::diff_cells(prev, &new_matrix),
+                    _ => Vec::new(),
+                };
+                self.previous_solution = Some(new_matrix.clone());
+            }
+
+            if solved {
+                sudoku::update_origins(&mut self.origins, &self.matrix, &new_matrix, sudoku::CellOrigin::Solver);
+            } else {
+                self.initial_matrix = Some(new_matrix.clone());
+                self.origins = sudoku::given_origins(&new_matrix);
+
+                if self.deal_animation {
+                    self.deal = Some(deal::Deal::new(&new_matrix, &mut rand::rng()));
+                }
+            }
+
+            self.matrix = new_matrix;
+            // Generated/solved boards are not player moves, so there is nothing left to take back.
+            self.player_moves.clear();
+            self.suggested_cells.clear();
+            self.redundant_clues.clear();
+            self.suspicious_cells.clear();
+            self.suspicious_msg = None;
+            self.browser = None;
+            println!("Received computation.");
+        }
+
+        // Check completition (if there is any) with non-blocking receive
+        if let Some(outcome) = poll(&mut self.rx_solved_by, &mut self.job_error) {
+            // Only set while a "Validate & Solve" runs.
+            if self.validation_msg.is_some() {
+                self.validation_msg = Some(match &outcome {
+                    Ok(_) => "Validation passed, solved.".to_string(),
+                    Err(stopped) => format!("\u{274C} Validate & Solve {}.", stopped),
+                });
+            }
+            if let Err(sudoku::SolveStopped::Conflicts(cells)) = &outcome {
+                self.invalid_poss = cells.clone();
+            }
+            self.solved_by = outcome.ok();
+        }
+
+        // Check completition (if there is any) with non-blocking receive
+        if let Some(elap_time) = poll(&mut self.rx_time, &mut self.job_error) {
+            self.solution_time = elap_time;
+            println!("Received time.");
+        }
+
+        // A solve whose board never came back failed: there is nothing to record.
+        if self.rx_matrix.is_none() && std::mem::take(&mut self.pending_solve) {
+            self.solving_algorithm = None;
+        }
+
+        // The three results of a solve can arrive on different frames.
+        if self.rx_matrix.is_none() && self.rx_time.is_none() && self.rx_solved_by.is_none()
+            && let Some(algorithm) = self.solving_algorithm.take() {
+            self.solve_history.push(history::SolveRecord {seconds: self.solution_time, algorithm, solved_by: self.solved_by});
+            self.best_outcome = self.best_times.record(self.matrix.len(), self.solution_time);
+
+            if self.solution_time.is_finite()
+                && let (Some(puzzle), Some(reference)) = (&self.initial_matrix, &self.reference) {
+                self.reference_check = Some(sudoku::compare_with_reference(puzzle, &self.matrix, reference));
+            }
+        }
+
+        if let Some(board) = poll(&mut self.rx_presentation, &mut self.job_error) {
+            self.presentation_board = board;
+        }
+
+        if let Some(result) = poll(&mut self.rx_region, &mut self.job_error) {
+            match result {
+                Some(board) => {
+                    sudoku::update_origins(&mut self.origins, &self.matrix, &board, sudoku::CellOrigin::Solver);
+                    self.matrix = board;
+                }
+                None => self.solution_time = f64::INFINITY, // Shows the unsolvable message
+            }
+        }
+
+        if let Some(completable) = poll(&mut self.rx_completable, &mut self.job_error) {
+            self.completable = Some(completable);
+        }
+
+        // Nothing else triggers a frame while a job runs in the background.
+        if self.is_computing() || self.rx_completable.is_some() {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+    }
+
+    // Arrow keys move the selection, starting from the top-left cell when nothing is selected.
+    fn handle_keyboard_navigation(&mut self, ctx: &egui::Context) {
+        if self.is_busy() || self.presentation_mode || ctx.wants_keyboard_input() {return}
+
+        const ARROW_KEYS: [(egui::Key, sudoku::Direction); 4] = [
+            (egui::Key::ArrowUp, sudoku::Direction::Up), (egui::Key::ArrowDown, sudoku::Direction::Down),
+            (egui::Key::ArrowLeft, sudoku::Direction::Left), (egui::Key::ArrowRight, sudoku::Direction::Right),
+        ];
+
+        let Some(direction) = ctx.input(|i| ARROW_KEYS.iter().find(|&&(key, _)| i.key_pressed(key)).map(|&(_, direction)| direction))
+            else {return};
+
+        self.selected_cell = Some(match self.selected_cell {
+            Some(pos) => sudoku::move_cell(self.matrix.len(), pos, direction),
+            None => (0, 0),
+        });
+        self.deal = None;
+    }
+
+    fn animate_step(&mut self, ctx: &egui::Context) {
+        let Some(stepper) = &mut self.stepper else {return};
+
+        if self.animation_paused {return}
+
+        let steps = steps_per_tick(self.step_delay_ms, self.last_step.elapsed());
+        if steps > 0 {
+            stepper.advance(steps);
+            self.last_step = Instant::now();
+            let board = stepper.board().to_matrix();
+            sudoku::update_origins(&mut self.origins, &self.matrix, &board, sudoku::CellOrigin::Solver);
+            self.matrix = board;
+        }
+
+        if stepper.is_finished() {
+            if stepper.step() == sudoku::BacktrackStep::Unsolvable {
+                self.solution_time = f64::INFINITY;
+            }
+            self.stop_animation();
+        } else {
+            ctx.request_repaint_after(Duration::from_millis(self.step_delay_ms));
+        }
+    }
+
+    /*
+        Ends the step-through solve, finished or not. The board keeps what the stepper wrote so far,
+        so moves (and suggestions) recorded before it no longer describe the cells and are dropped.
+    */
+    fn stop_animation(&mut self) {
+        self.stepper = None;
+        self.player_moves.clear();
+        self.suggested_cells.clear();
+    }
+
+    // With `validate`, the clues are checked first and nothing is solved if one breaks a rule.
+    fn start_solve(&mut self, algorithm: sudoku::Algorithm, validate: bool) {
+
+        // Creating a message channel for non-blocking matrix receive.
+        let (tx_matrix, rx_matrix) = mpsc::channel::<Vec<Vec<i8>>>();
+
+        // Creating another message channel for non-blocking time receive.
+        let (tx_time, rx_time) = mpsc::channel::<f64>();
+
+        // And one for the solver that actually succeeded (differs from `algorithm` on fallback).
+        // Or which stage stopped it.
+        let (tx_solved_by, rx_solved_by) = mpsc::channel::<Result<sudoku::SolvedBy, sudoku::SolveStopped>>();
+
+        // Cloning self data since borrowing would escape from the method (error from compiler).
+        let mut matrix_clone = self.matrix.clone();
+        let fallback_steps = self.fallback_steps;
+        let clues = self.clue_encoding;
+        let constraints = self.solving_constraints();
+        let max_size = self.max_matrix_size.pow(2);
+
+        // Execute algorithm on a separate thread (still sequentially)
+        // This is needed to avoid GUI freezes for long computations.
+        self.submit(move |_| {
+            let start = Instant::now();
+
+            // The buttons are already off above the cap: this is the last line before any formula is built.
+            let outcome = if validate {
+                sudoku::validate_and_solve(&mut matrix_clone, algorithm, &constraints, fallback_steps, clues)
+            } else {
+                sudoku::solve_capped(&mut matrix_clone, algorithm, &constraints, fallback_steps, clues, max_size)
+                    .unwrap_or_else(|err| {
+                        println!("Cannot solve: {}", err);
+                        None
+                    })
+                    .ok_or(sudoku::SolveStopped::Unsolvable)
+            };
+            let time = if outcome.is_ok() {start.elapsed().as_secs_f64()} else {f64::INFINITY};
+
+            tx_time.send(time).unwrap();
+            tx_solved_by.send(outcome).unwrap();
+            tx_matrix.send(matrix_clone).unwrap();
+        });
+
+        self.rx_matrix = Some(rx_matrix);
+        self.rx_time = Some(rx_time);
+        self.rx_solved_by = Some(rx_solved_by);
+        self.pending_solve = true;
+        self.solving_algorithm = Some(algorithm);
+        self.validation_msg = validate.then(|| "Validating the clues...".to_string());
+        self.best_outcome = None;
+        self.reference_check = None;
+    }
+
+    // The variant rules, plus the pencil marks when the player asked to have them honored.
+    fn solving_constraints(&self) -> sudoku::ConstraintSet {
+        let mut constraints = self.constraints.clone();
+        if self.respect_pencil_marks {
+            constraints.pencil_marks = self.pencil_marks.clone();
+        }
+        constraints
+    }
+
+    // Whether `rules` equal solving_constraints(), without building (cloning) them.
+    fn solves_with(&self, rules: &sudoku::ConstraintSet) -> bool {
+        let marks = if self.respect_pencil_marks {&self.pencil_marks} else {&self.constraints.pencil_marks};
+
+        rules.inequalities == self.constraints.inequalities
+            && rules.variants == self.constraints.variants
+            && rules.regions == self.constraints.regions
+            && rules.pencil_marks == *marks
+    }
+
+    /*
+        Checks on the worker whether the board can still be completed, once editing paused for DEAD_END_CHECK_DELAY.
+        Nothing is checked while the board and the rules match the last check.
+    */
+    fn schedule_dead_end_check(&mut self, ctx: &egui::Context) {
+        let checked = self.completable.as_ref().is_some_and(|(board, rules, _)| *board == self.matrix && self.solves_with(rules));
+        if checked || self.rx_completable.is_some() {return}
+
+        let unchanged_for = match &self.dead_end_settle {
+            Some((board, since)) if *board == self.matrix => since.elapsed(),
+            _ => {
+                self.dead_end_settle = Some((self.matrix.clone(), Instant::now()));
+                Duration::ZERO
+            }
+        };
+
+        if unchanged_for < DEAD_END_CHECK_DELAY {
+            ctx.request_repaint_after(DEAD_END_CHECK_DELAY - unchanged_for);
+            return;
+        }
+
+        let (tx, rx) = mpsc::channel::<Completable>();
+        let (board, rules) = (self.matrix.clone(), self.solving_constraints());
+
+        // Straight to the worker: a background check shouldn't clear the error of a job the user started.
+        self.worker.submit(move |_| {
+            let completable = sudoku::is_completable_with(&board, &rules);
+            tx.send((board, rules, completable)).unwrap();
+        });
+
+        self.rx_completable = Some(rx);
+        self.dead_end_settle = None;
+    }
+
+    // Replaces the board (and its size) with an externally provided puzzle, unless the size slider can't show it.
+    fn load_puzzle(&mut self, matrix: Vec<Vec<i8>>) -> Result<(), sudoku::SizeError> {
+        sudoku::assert_board_within(&matrix, self.max_matrix_size.pow(2))?;
+
+        let cells: usize = matrix.iter().map(Vec::len).sum();
+        self.matrix_size = sudoku::infer_size(cells).ok_or(sudoku::SizeError::NotSquare(matrix.len()))?;
+
+        self.update_matrix();
+        self.initial_matrix = Some(matrix.clone());
+        self.origins = sudoku::given_origins(&matrix);
+        self.matrix = matrix;
+        Ok(())
+    }
+
+    // Back to the generated/loaded puzzle, dropping solutions and player edits but not settings.
+    fn revert_to_initial(&mut self) {
+        let Some(initial) = &self.initial_matrix else {return};
+
+        self.matrix = initial.clone();
+        self.origins = sudoku::given_origins(initial);
+        self.invalid_poss.clear();
+        self.redundant_clues.clear();
+        self.suspicious_cells.clear();
+        self.suspicious_msg = None;
+        self.changed_cells.clear();
+        self.show_correctness = false;
+        self.solution_time = f64::NAN;
+        self.solved_by = None;
+        self.player_moves.clear();
+        self.suggested_cells.clear();
+    }
+
+    // Where an export named `file_name` goes: inside export_dir, unless `file_name` is an absolute path.
+    fn export_path(&self, file_name: &str) -> std::path::PathBuf {
+        std::path::Path::new(self.export_dir.trim()).join(file_name)
+    }
+
+    fn current_replay(&self) -> replay::Replay {
+        replay::Replay {
+            puzzle: sudoku::export_puzzle_to_string(&self.matrix),
+            size: self.matrix.len(),
+            algorithm: self.default_algorithm,
+            rules: replay::Rules::from(&self.solving_constraints()),
+            clues: self.clue_encoding,
+            fallback_steps: self.fallback_steps,
+            seed: self.puzzle_seed,
+        }
+    }
+
+    // Loads the replay's board and selects its solver and rules, ready to press "Solve".
+    fn apply_replay(&mut self, replay: replay::Replay) -> std::io::Result<()> {
+        let matrix = replay.matrix()?;
+        let mut constraints = replay.rules.to_constraints()?;
+
+        if let Some(regions) = &constraints.regions && regions.size() != matrix.len() {
+            let msg = format!("the regions are for {0}x{0} boards, not {1}x{1}", regions.size(), matrix.len());
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, msg));
+        }
+
+        self.load_puzzle(matrix).map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+        self.default_algorithm = replay.algorithm;
+
+        // Recorded pencil marks were solving rules, so they come back as respected marks.
+        self.pencil_marks = std::mem::take(&mut constraints.pencil_marks);
+        self.respect_pencil_marks = !self.pencil_marks.is_empty();
+        self.constraints = constraints;
+        self.clue_encoding = replay.clues;
+        self.fallback_steps = replay.fallback_steps;
+        self.puzzle_seed = replay.seed;
+
+        Ok(())
+    }
+
+    fn save_favorites(&self) {
+        if let Err(err) = favorites::save_favorites(&self.favorites) {
+            println!("Could not save favorites: {}", err);
+        }
+    }
+
+    // Consecutive edits of the same cell count as a single move, keeping the value it had before.
+    fn record_move(&mut self, row: usize, col: usize, prev_value: i8) {
+        // Editing a browsed solution makes it the player's grid.
+        self.browser = None;
+        self.validation_msg = None;
+        self.suggested_cells.retain(|&cell| cell != (row, col));
+        self.player_moves.record((row, col), prev_value);
+    }
+
+    // A player placement (or clear, with 0) outside the edit popup.
+    fn set_cell(&mut self, row: usize, col: usize, value: i8) {
+        self.record_move(row, col, self.matrix[row][col]);
+        self.matrix[row][col] = value;
+        self.origins[row][col] = sudoku::CellOrigin::for_value(value, sudoku::CellOrigin::Player);
+        self.show_correctness = false;
+        self.invalid_poss.clear();
+        self.redundant_clues.clear();
+        self.suspicious_cells.clear();
+        self.suspicious_msg = None;
+        self.changed_cells.clear();
+    }
+
+    fn take_back_move(&mut self) {
+        if let Some(((row, col), prev_value)) = self.player_moves.take_back(&mut self.matrix) {
+            // Moves are cleared by every solve, so the previous value was either a clue or the player's.
+            let is_given = self.initial_matrix.as_ref().is_some_and(|initial| initial[row][col] == prev_value);
+            self.origins[row][col] = sudoku::CellOrigin::for_value(prev_value, if is_given {sudoku::CellOrigin::Given} else {sudoku::CellOrigin::Player});
+            self.suggested_cells.retain(|&cell| cell != (row, col));
+            self.show_correctness = false;
+            self.invalid_poss.clear();
+        }
     }
 }
 
 impl App for MatrixApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, DEFAULT_ALGORITHM_KEY, &self.default_algorithm);
+        eframe::set_value(storage, BEST_TIMES_KEY, &self.best_times);
+        eframe::set_value(storage, PANEL_ON_LEFT_KEY, &self.panel_on_left);
+        eframe::set_value(storage, MAX_MATRIX_SIZE_KEY, &self.max_matrix_size);
+        eframe::set_value(storage, EXPORT_DIR_KEY, &self.export_dir);
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        egui::SidePanel::right("right_panel")
+        self.poll_results(ctx);
+        self.animate_step(ctx);
+
+        // Leaving is always allowed; entering waits for the running job or animation, which owns the grid.
+        if ctx.input(|i| i.key_pressed(egui::Key::F5)) && (self.presentation_mode || !self.is_busy()) {
+            self.toggle_presentation();
+        }
+
+        self.handle_keyboard_navigation(ctx);
+        self.handle_keyboard_entry(ctx);
+
+        // The central panel takes whatever space the side panel leaves, on either side.
+        let side_panel = if self.panel_on_left {egui::SidePanel::left("left_panel")} else {egui::SidePanel::right("right_panel")};
+
+        side_panel
         .max_width(350.)
-        .show(ctx, |ui| {
+        .show_animated(ctx, !self.presentation_mode, |ui| {
 
             ctx.set_pixels_per_point(self.ui_scale);
             ctx.set_visuals( if self.dark_mode {egui::Visuals::dark()} else {egui::Visuals::light()});
@@ -104,88 +945,651 @@ impl App for MatrixApp {
                     egui::Checkbox::new(&mut self.dark_mode, "Dark mode")
                 );
 
+                ui.add(
+                    egui::Checkbox::new(&mut self.show_coordinates, "Show coordinates")
+                );
+
+                ui.add(
+                    egui::Checkbox::new(&mut self.panel_on_left, "Panel on the left")
+                );
+
+                egui::ComboBox::from_label("Blank cells")
+                .selected_text(self.blank_style.to_string())
+                .show_ui(ui, |ui| {
+                    for style in sudoku::BlankStyle::ALL {
+                        ui.selectable_value(&mut self.blank_style, style, style.to_string());
+                    }
+                });
+
+                ui.add(egui::Checkbox::new(&mut self.auto_advance, "Auto-advance after typing a digit"))
+                    .on_hover_text("On boards past 9x9, type A for 10, B for 11 and so on");
+                ui.add(egui::Checkbox::new(&mut self.warn_dead_ends, "Warn when the board can't be completed"))
+                    .on_hover_text(format!("Up to {0}x{0} boards", DEAD_END_CHECK_MAX_SIZE));
+                ui.add(egui::Checkbox::new(&mut self.deal_animation, "Deal generated puzzles")).on_hover_text("Clues appear one by one, click the grid to skip");
+                ui.add_enabled(self.auto_advance, egui::Checkbox::new(&mut self.advance_wraps, "Wrap to the first cell at the end"));
+
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!self.is_busy(), egui::Button::new("\u{1F5B5} Presentation mode")).on_hover_text("Only the grid, read-only. F5 toggles it").clicked() {
+                        self.toggle_presentation();
+                    }
+                    ui.checkbox(&mut self.present_solution, "Solved");
+                });
+
+                ui.add_space(10.);
+
+                if ui.add_enabled(!self.is_busy(), egui::Slider::new(&mut self.matrix_size, 1..=self.max_matrix_size).text("Matrix Size")).changed() {
+                    self.update_matrix();
+                }
+
+                let busy = self.is_busy();
+                if ui.add_enabled(!busy, egui::Slider::new(&mut self.max_matrix_size, 1..=5).text("Max Matrix Size")).on_hover_text("Larger boards can't be created or solved").changed()
+                    && self.matrix_size > self.max_matrix_size {
+                    self.matrix_size = self.max_matrix_size;
+                    self.update_matrix();
+                }
+
+                ui.add_space(10.);
+
+                // Variants combine: each one only adds cells that can't share a value.
+                for variant in sudoku::Variant::ALL {
+                    let mut enabled = self.constraints.has(variant);
+                    if ui.add_enabled(!self.is_busy(), egui::Checkbox::new(&mut enabled, format!("{} variant", variant))).changed() {
+                        self.constraints.set_variant(variant, enabled);
+                    }
+                }
+
+                egui::CollapsingHeader::new("Jigsaw regions").show(ui, |ui| {
+                    ui.label("One character per cell, row by row; cells sharing a character form a region.");
+                    ui.add(egui::TextEdit::multiline(&mut self.region_map).font(egui::TextStyle::Monospace).desired_rows(4));
+
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(!self.is_busy(), egui::Button::new("Apply Regions")).clicked() {
+                            self.regions_msg = Some(match sudoku::Regions::from_map(&self.region_map) {
+                                Ok(regions) if regions.size() != self.matrix.len() =>
+                                    format!("The map is for {0}x{0} boards, not {1}x{1}.", regions.size(), self.matrix.len()),
+                                Ok(regions) => {
+                                    self.constraints.regions = Some(regions);
+                                    "Regions replace the boxes.".to_string()
+                                }
+                                Err(err) => format!("Invalid map: {}.", err),
+                            });
+                        }
+
+                        if ui.add_enabled(!self.is_busy() && self.constraints.regions.is_some(), egui::Button::new("Back to Boxes")).clicked() {
+                            self.constraints.regions = None;
+                            self.regions_msg = None;
+                        }
+                    });
+
+                    if let Some(msg) = &self.regions_msg {
+                        ui.label(msg);
+                    }
+                });
+
+                ui.add_space(10.);
+
+                //Show Ctrl/Cmd according to OS, using macos as target for cmd.
+                egui::ComboBox::from_label(format!("Zoom factor {}", if cfg!(target_os = "macos") {"(Cmd -/+)"} else {"(Ctrl -/+)"}))
+                .selected_text(format!("{:?}", self.ui_scale))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.ui_scale, 0.8, "Small");
+                    ui.selectable_value(&mut self.ui_scale, 1., "Regular");
+                    ui.selectable_value(&mut self.ui_scale, 1.5, "Big");
+                    ui.selectable_value(&mut self.ui_scale, 2., "Huge");
+                });
+
+                ui.add_space(10.);
+
+                // Lower bound keeps the digits readable on a 25x25 board.
+                ui.add(egui::Slider::new(&mut self.grid_scale, MIN_GRID_SCALE..=MAX_GRID_SCALE).text("Grid zoom"));
+
+                ui.add_space(10.);
+
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut self.sat_display_limit).range(100..=1_000_000).speed(100));
+                    ui.label("SAT display limit (clauses)");
+                });
+
+                ui.add_space(10.);
+
+                if ui.button("\u{2139} About").clicked() {
+                    self.show_about = true;
+                }
+
+                ui.add_space(10.);
+
+                ui.separator();
+
+                ui.add_space(10.);
+
+                //if(!self.is_busy())
+
+                ui.label(
+                    egui::RichText::new("Operations")
+                        .size(20.0)
+                        .strong()
+                        .monospace()
+                );
+
+                ui.add_space(10.);
+
+                if ui.add_enabled(!self.is_busy(), egui::Button::new("\u{1F3B2} Generate Random Puzzle")).clicked() {
+
+                    // Creating a message channel for non-blocking matrix receive.
+                    let (tx, rx) = mpsc::channel::<Vec<Vec<i8>>>();
+
+                    // Reset matrix
+                    self.update_matrix();
+
+                    // Cloning self data since borrowing would escape from the method (error from compiler).
+                    let mut matrix_clone = self.matrix.clone();
+                    let seed_size = self.matrix_size.pow(2) * 2;
+
+                    // Execute algorithm on a separate thread (still sequentially)
+                    // This is needed to avoid GUI freezes for long computations.
+                    self.submit(move |_| {
+                        sudoku::generate_random_matrix(&mut matrix_clone, seed_size);
+                        tx.send(matrix_clone).unwrap();
+                    });
+
+                    self.rx_matrix = Some(rx);
+
+                }
+
+                ui.add_space(10.);
+
+                ui.horizontal(|ui| {
+                    // A running generation can be restarted, e.g. after changing the clue count: the old one is abandoned.
+                    let generating = self.rx_generation.is_some();
+
+                    if ui.add_enabled(!self.is_busy() || generating, egui::Button::new("\u{1F3B2} Generate Unique Puzzle")).clicked() {
+                        let (tx, rx) = mpsc::channel::<sudoku::GenerationResult>();
+
+                        self.worker.cancel();
+                        self.rx_generation = None;
+
+                        self.update_matrix();
+                        self.generation_msg = None;
+
+                        let (size, target) = (self.matrix.len(), self.target_clues);
+                        let order = sudoku::RemovalOrder::Shuffled(rand::random());
+
+                        self.submit(move |cancel| {
+                            // A cancelled run sends nothing, and a stale receiver is already dropped.
+                            if let Some(result) = sudoku::generate_with_clues_cancellable(size, target, order, cancel) {
+                                let _ = tx.send(result);
+                            }
+                        });
+
+                        self.rx_generation = Some(rx);
+                    }
+
+                    ui.add(egui::DragValue::new(&mut self.target_clues).range(0..=625));
+                    ui.label("clues");
+
+                    if generating && ui.button("\u{23F9} Cancel").clicked() {
+                        self.worker.cancel();
+                        self.rx_generation = None;
+                        self.generation_msg = Some("Generation cancelled.".to_string());
+                    }
+                });
+
+                if let Some(msg) = &self.generation_msg {
+                    ui.label(msg);
+                }
+
+                ui.add_space(10.);
+
+                // The daily puzzle is always 9x9, which a lower size cap rules out.
+                let daily_fits = self.max_matrix_size >= 3;
+                let mut daily = ui.add_enabled(!self.is_busy() && daily_fits, egui::Button::new("\u{1F4C5} Daily Puzzle"));
+                if !daily_fits {
+                    daily = daily.on_disabled_hover_text("The daily puzzle is 9x9, above the size cap");
+                }
+
+                if daily.clicked() {
+
+                    let (tx, rx) = mpsc::channel::<Vec<Vec<i8>>>();
+
+                    self.matrix_size = 3;
+                    self.update_matrix();
+
+                    let today = chrono::Local::now().date_naive();
+                    self.puzzle_seed = Some(sudoku::daily_seed(today));
+
+                    self.submit(move |_| {
+                        tx.send(sudoku::puzzle_of_the_day(today)).unwrap();
+                    });
+
+                    self.rx_matrix = Some(rx);
+                }
+
+                ui.add_space(10.);
+
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!self.is_busy(), egui::Button::new("\u{1F3AF} Practice")).clicked() {
+                        let (tx, rx) = mpsc::channel::<Result<Vec<Vec<i8>>, sudoku::GenerationError>>();
+
+                        self.update_matrix();
+                        self.practice_msg = None;
+
+                        let technique = self.practice_technique;
+                        let size = self.matrix.len();
+
+                        self.submit(move |_| {
+                            tx.send(sudoku::generate_for_technique(technique, size)).unwrap();
+                        });
+
+                        self.rx_practice = Some(rx);
+                    }
+
+                    egui::ComboBox::from_id_salt("practice_technique")
+                    .selected_text(self.practice_technique.to_string())
+                    .show_ui(ui, |ui| {
+                        // Guessing isn't a skill to practice
+                        for technique in sudoku::Technique::ALL.into_iter().filter(|&technique| technique != sudoku::Technique::Guess) {
+                            ui.selectable_value(&mut self.practice_technique, technique, technique.to_string());
+                        }
+                    });
+                }).response.on_hover_text("A puzzle whose hardest required technique is the selected one");
+
+                if let Some(msg) = &self.practice_msg {
+                    ui.label(msg);
+                }
+
                 ui.add_space(10.);
 
-                if ui.add_enabled(self.rx_matrix.is_none(), egui::Slider::new(&mut self.matrix_size, 1..=5).text("Matrix Size")).changed() {
+                if ui.add_enabled(!self.is_busy(), egui::Button::new("\u{1F504} Reset Grid")).clicked() {
                     self.update_matrix();
+                    self.solve_history.clear();
+                }
+
+                ui.add_space(10.);
+
+                if ui.add_enabled(!self.is_busy() && self.initial_matrix.is_some(), egui::Button::new("\u{23EA} Revert to Puzzle")).clicked() {
+                    self.revert_to_initial();
+                }
+
+                ui.add_space(10.);
+
+                if ui.add_enabled(!self.is_busy() && !self.player_moves.is_empty(), egui::Button::new("\u{21A9} Take Back Move")).clicked() {
+                    self.take_back_move();
+                }
+
+                ui.add_space(10.);
+
+                if ui.add_enabled(!self.is_busy(), egui::Button::new("\u{1F3AF} Suggest a Move"))
+                    .on_hover_text("Fills a random cell with a legal value, not necessarily the right one")
+                    .clicked()
+                    && let Some(((row, col), value)) = sudoku::suggest_move_with(&self.matrix, &self.solving_constraints()) {
+                    self.record_move(row, col, 0);
+                    self.matrix[row][col] = value;
+                    self.origins[row][col] = sudoku::CellOrigin::Player;
+                    self.suggested_cells.push((row, col));
+                    self.show_correctness = false;
+                    self.invalid_poss.clear();
+                }
+
+                ui.add_space(10.);
+
+                // Found with the classic boxes, so it would mislead on a jigsaw board.
+                ui.add_enabled(self.constraints.regions.is_none(), egui::Checkbox::new(&mut self.show_easy_moves, "\u{1F4A1} Show easy moves"))
+                    .on_hover_text("Last empty cell of a row, column or block");
+
+                ui.add_space(10.);
+
+                // On-screen keypad for touch devices, where right-click editing isn't available.
+                if let Some((row, col)) = self.selected_cell {
+                    ui.label(format!("Keypad for ({}, {})", row, col));
+
+                    let mut entered = None;
+                    let counts = sudoku::digit_counts(&self.matrix);
+
+                    ui.add_enabled_ui(!self.is_busy(), |ui| {
+                        egui::Grid::new("keypad_grid").spacing([4., 4.]).show(ui, |ui| {
+                            for value in 1..=self.matrix_size.pow(2) as i8 {
+                                // Complete digits (placed once per row) are dimmed: they have nowhere left to go.
+                                let count = counts[sudoku::value_to_index(value)];
+                                let text = egui::RichText::new(value.to_string());
+                                let text = if count >= counts.len() {text.color(ui.visuals().weak_text_color())} else {text};
+
+                                if ui.add(egui::Button::new(text).min_size(egui::vec2(28., 28.)))
+                                    .on_hover_text(format!("{}/{} placed", count, counts.len()))
+                                    .clicked() {
+                                    entered = Some(value);
+                                }
+                                if (value as usize).is_multiple_of(self.matrix_size) {ui.end_row()}
+                            }
+                        });
+
+                        if ui.button("\u{232B} Clear").clicked() {
+                            entered = Some(0);
+                        }
+                    });
+
+                    if let Some(value) = entered {
+                        self.set_cell(row, col, value);
+                    }
+
+                    ui.add_space(10.);
+                }
+
+                ui.horizontal(|ui| {
+                    let max_digit = self.matrix_size.pow(2) as i8;
+                    ui.add(egui::DragValue::new(&mut self.swap_pair.0).range(1..=max_digit));
+                    ui.label("\u{2194}");
+                    ui.add(egui::DragValue::new(&mut self.swap_pair.1).range(1..=max_digit));
+
+                    if ui.add_enabled(!self.is_busy(), egui::Button::new("Swap Digits")).clicked()
+                        && sudoku::swap_digits(&mut self.matrix, self.swap_pair.0, self.swap_pair.1) {
+                        // Not a player move: taking back a single cell would now mix both labelings.
+                        self.player_moves.clear();
+                        self.suggested_cells.clear();
+                        self.show_correctness = false;
+                        self.invalid_poss.clear();
+                    }
+                });
+                ui.add_space(10.);
+
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut self.image_path).hint_text("Image, .ss or .csv path").desired_width(120.));
+
+                    if ui.add_enabled(!self.is_busy(), egui::Button::new("\u{1F4F7} Import from File")).clicked() {
+                        let path = std::path::Path::new(self.image_path.trim());
+
+                        // Simple Sudoku files are text, anything else goes through OCR.
+                        // A CSV brings its first puzzle along with the reference solution.
+                        let has_extension = |name: &str| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case(name));
+
+                        let imported = if has_extension("ss") {
+                            sudoku::load_ss(path).map(|matrix| (matrix, None)).map_err(|err| err.to_string())
+                        } else if has_extension("csv") {
+                            sudoku::load_csv(path)
+                                .map_err(|err| err.to_string())
+                                .and_then(|records| records.into_iter().next().ok_or_else(|| "no puzzle in the file".to_string()))
+                                .map(|record| (record.puzzle, Some(record.solution)))
+                        } else {
+                            sudoku::ocr::import_from_image(&sudoku::ocr::SidecarOcr, path).map(|matrix| (matrix, None)).map_err(|err| err.to_string())
+                        };
+
+                        match imported.and_then(|(matrix, reference)| self.load_puzzle(matrix).map(|()| reference).map_err(|err| err.to_string())) {
+                            Ok(reference) => {
+                                self.reference = reference;
+                                self.import_msg = None;
+                            }
+                            Err(err) => self.import_msg = Some(format!("Import failed: {}", err)),
+                        }
+                    }
+
+                    if ui.add_enabled(!self.is_busy(), egui::Button::new("\u{1F4F1} Scan QR")).on_hover_text("Read a QR code from an image file").clicked() {
+                        self.import_msg = match sudoku::qr::puzzle_from_qr_image(std::path::Path::new(self.image_path.trim())) {
+                            Ok(matrix) => self.load_puzzle(matrix).err().map(|err| format!("Import failed: {}", err)),
+                            Err(err) => Some(format!("Import failed: {}", err)),
+                        };
+                    }
+                });
+
+                if let Some(msg) = &self.import_msg {
+                    ui.label(egui::RichText::new(msg).color(egui::Color32::DARK_RED));
                 }
+                ui.add_space(10.);
+
+                let bulk_btn = ui.add_enabled(!self.is_busy(), egui::Button::new("\u{270F} Bulk Edit"));
+
+                egui::Popup::menu(&bulk_btn)
+                    .close_behavior(egui::PopupCloseBehavior::CloseOnClickOutside)
+                    .show(|ui| {
+                        let size = self.matrix.len();
+                        ui.label(format!("{} rows of {} values, separated by spaces or commas (0 or . for blanks)", size, size));
+
+                        ui.add(egui::TextEdit::multiline(&mut self.bulk_text)
+                            .font(egui::TextStyle::Monospace)
+                            .desired_rows(size.min(16))
+                            .desired_width(300.));
+
+                        if ui.button("Apply").clicked() {
+                            match sudoku::parse_grid_rows(&self.bulk_text, size) {
+                                Ok(matrix) => self.bulk_msg = self.load_puzzle(matrix).err().map(|err| format!("Invalid grid: {}", err)),
+                                Err(err) => self.bulk_msg = Some(format!("Invalid grid: {}", err)),
+                            }
+                        }
+
+                        if let Some(msg) = &self.bulk_msg {
+                            ui.label(egui::RichText::new(msg).color(ui.visuals().error_fg_color));
+                        }
+                    });
+
+                ui.add_space(10.);
+
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut self.favorite_name).hint_text("Name (optional)").desired_width(120.));
+
+                    if ui.add_enabled(!self.is_busy(), egui::Button::new("\u{2B50} Star Puzzle")).clicked() {
+                        let name = self.favorite_name.trim();
+                        self.favorites.push(favorites::Favorite {
+                            name: if name.is_empty() {None} else {Some(name.to_string())},
+                            puzzle: sudoku::export_puzzle_to_string(&self.matrix),
+                        });
+                        self.favorite_name.clear();
+                        self.save_favorites();
+                    }
+
+                    if ui.button("\u{1F4CB} Copy Puzzle").on_hover_text("Copy as a one-line string, blanks as set in the settings").clicked() {
+                        ctx.copy_text(sudoku::export_puzzle_to_string_with(&self.matrix, self.blank_style));
+                    }
+
+                    let qr_btn = ui.button("\u{1F4F1} Show QR");
+
+                    egui::Popup::menu(&qr_btn)
+                        .close_behavior(egui::PopupCloseBehavior::CloseOnClickOutside)
+                        .show(|ui| {
+                            if self.qr.as_ref().is_none_or(|(board, _)| *board != self.matrix) {
+                                self.qr = Some((self.matrix.clone(), sudoku::qr::puzzle_to_qr(&self.matrix).map_err(|err| err.to_string())));
+                                self.qr_msg = None;
+                            }
+
+                            match &self.qr {
+                                Some((_, Ok(code))) => {
+                                    // Light margin included, scanners need it on dark themes too.
+                                    let module = 4.;
+                                    let side = (code.size() + 2 * sudoku::qr::QUIET_ZONE) as f32 * module;
+                                    let (rect, _) = ui.allocate_exact_size(egui::vec2(side, side), egui::Sense::hover());
+
+                                    ui.painter().rect_filled(rect, 0., egui::Color32::WHITE);
+                                    for y in 0..code.size() {
+                                        for x in 0..code.size() {
+                                            if code.is_dark(x, y) {
+                                                let min = rect.min + egui::vec2((x + sudoku::qr::QUIET_ZONE) as f32, (y + sudoku::qr::QUIET_ZONE) as f32) * module;
+                                                ui.painter().rect_filled(egui::Rect::from_min_size(min, egui::vec2(module, module)), 0., egui::Color32::BLACK);
+                                            }
+                                        }
+                                    }
 
-                ui.add_space(10.);
+                                    if ui.button("\u{1F4BE} Save QR").clicked() {
+                                        let path = self.export_path("sudoku_qr.png");
+                                        self.qr_msg = Some(match code.save_png(&path, 8) {
+                                            Ok(()) => format!("Saved to {}", path.display()),
+                                            Err(err) => format!("Export failed: {}", err),
+                                        });
+                                    }
+                                }
+                                Some((_, Err(err))) => {ui.label(egui::RichText::new(err).color(ui.visuals().error_fg_color));}
+                                None => {}
+                            }
 
-                //Show Ctrl/Cmd according to OS, using macos as target for cmd.
-                egui::ComboBox::from_label(format!("Zoom factor {}", if cfg!(target_os = "macos") {"(Cmd -/+)"} else {"(Ctrl -/+)"}))
-                .selected_text(format!("{:?}", self.ui_scale))
-                .show_ui(ui, |ui| {
-                    ui.selectable_value(&mut self.ui_scale, 0.8, "Small");
-                    ui.selectable_value(&mut self.ui_scale, 1., "Regular");
-                    ui.selectable_value(&mut self.ui_scale, 1.5, "Big");
-                    ui.selectable_value(&mut self.ui_scale, 2., "Huge");
+                            if let Some(msg) = &self.qr_msg {
+                                ui.label(msg);
+                            }
+                        });
                 });
 
                 ui.add_space(10.);
 
-                ui.separator();
+                ui.horizontal(|ui| {
+                    let mut picked = None;
 
-                ui.add_space(10.);
+                    egui::ComboBox::from_label("Favorites")
+                    .selected_text(self.selected_favorite.and_then(|i| self.favorites.get(i)).map_or("-", |fav| fav.label()))
+                    .show_ui(ui, |ui| {
+                        for (i, fav) in self.favorites.iter().enumerate() {
+                            if ui.selectable_label(self.selected_favorite == Some(i), fav.label()).clicked() {
+                                picked = Some(i);
+                            }
+                        }
+                    });
 
-                //if(self.rx_matrix.is_none())
+                    if let Some(i) = picked && !self.is_busy() {
+                        match sudoku::parse_puzzle(&self.favorites[i].puzzle) {
+                            Ok(matrix) => match self.load_puzzle(matrix) {
+                                Ok(()) => {
+                                    self.selected_favorite = Some(i);
+                                    self.import_msg = None;
+                                }
+                                Err(err) => self.import_msg = Some(format!("Favorite not loaded: {}", err)),
+                            },
+                            Err(err) => self.import_msg = Some(format!("Favorite not loaded: {}", err)),
+                        }
+                    }
 
-                ui.label(
-                    egui::RichText::new("Operations")
-                        .size(20.0)
-                        .strong()
-                        .monospace()
-                );
+                    if ui.add_enabled(self.selected_favorite.is_some(), egui::Button::new("\u{1F5D1}")).on_hover_text("Remove from favorites").clicked()
+                        && let Some(i) = self.selected_favorite.take() {
+                        self.favorites.remove(i);
+                        self.save_favorites();
+                    }
+                });
 
                 ui.add_space(10.);
 
-                if ui.add_enabled(self.rx_matrix.is_none(), egui::Button::new("\u{1F3B2} Generate Random Puzzle")).clicked() {
+                ui.horizontal(|ui| {
+                    ui.label("Export folder:");
+                    ui.add(egui::TextEdit::singleline(&mut self.export_dir).desired_width(180.))
+                        .on_hover_text("CNF, graph, PDF, QR and replay files are saved here (replays are also loaded from it)");
+                });
 
-                    // Creating a message channel for non-blocking matrix receive.
-                    let (tx, rx) = mpsc::channel::<Vec<Vec<i8>>>();
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.pdf_options.include_solutions, "With solutions");
+                    ui.add(egui::DragValue::new(&mut self.pdf_options.puzzles_per_page).range(1..=6));
+                    ui.label("per page");
+                });
 
-                    // Reset matrix
-                    self.update_matrix();
+                ui.horizontal(|ui| {
+                    let mut worksheet: Option<Vec<Vec<Vec<i8>>>> = None;
 
-                    // Cloning self data since borrowing would escape from the method (error from compiler).
-                    let mut matrix_clone = self.matrix.clone();
-                    let seed_size = self.matrix_size.pow(2) * 2;
+                    if ui.add_enabled(!self.is_busy(), egui::Button::new("\u{1F5B6} Export PDF")).clicked() {
+                        worksheet = Some(vec![self.matrix.clone()]);
+                    }
 
-                    // Execute algorithm on a separate thread (still sequentially)
-                    // This is needed to avoid GUI freezes for long computations.
-                    std::thread::spawn(move || {
-                        sudoku::generate_random_matrix(&mut matrix_clone, seed_size);
-                        tx.send(matrix_clone).unwrap();
-                    });
+                    if ui.add_enabled(!self.is_busy() && !self.favorites.is_empty(), egui::Button::new("\u{1F5B6} Export Favorites")).clicked() {
+                        worksheet = Some(self.favorites.iter().filter_map(|fav| sudoku::parse_puzzle(&fav.puzzle).ok()).collect());
+                    }
 
-                    self.rx_matrix = Some(rx);
+                    if let Some(puzzles) = worksheet {
+                        let path = self.export_path("sudoku.pdf");
+                        self.pdf_export_msg = Some(match sudoku::pdf::export_pdf(&puzzles, &self.pdf_options, &path) {
+                            Ok(()) => format!("Saved to {}", path.display()),
+                            Err(err) => format!("Export failed: {}", err),
+                        });
+                    }
+                });
 
+                if let Some(msg) = &self.pdf_export_msg {
+                    ui.label(msg);
                 }
 
                 ui.add_space(10.);
 
-                if ui.add_enabled(self.rx_matrix.is_none(), egui::Button::new("\u{1F504} Reset Grid")).clicked() {
-                    self.update_matrix();
+                // Board, solver and rules in one file, to attach to bug reports.
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut self.replay_path).hint_text(DEFAULT_REPLAY_PATH).desired_width(120.));
+
+                    let path_text = if self.replay_path.trim().is_empty() {DEFAULT_REPLAY_PATH} else {self.replay_path.trim()};
+                    let path = self.export_path(path_text);
+
+                    if ui.add_enabled(!self.is_busy(), egui::Button::new("\u{1F4BE} Save Replay")).clicked() {
+                        self.replay_msg = Some(match replay::save_replay(&self.current_replay(), &path) {
+                            Ok(()) => format!("Saved to {}", path.display()),
+                            Err(err) => format!("Save failed: {}", err),
+                        });
+                    }
+
+                    if ui.add_enabled(!self.is_busy(), egui::Button::new("\u{1F4C2} Load Replay")).clicked() {
+                        self.replay_msg = match replay::load_replay(&path).and_then(|replay| self.apply_replay(replay)) {
+                            Ok(()) => None,
+                            Err(err) => Some(format!("Load failed: {}", err)),
+                        };
+                    }
+                });
+
+                if let Some(msg) = &self.replay_msg {
+                    ui.label(msg);
                 }
 
                 ui.add_space(10.);
 
-                let sat_btn = ui.add_enabled(self.rx_matrix.is_none(), egui::Button::new("\u{2139} Show SAT Reduction"));
+                let sat_btn = ui.add_enabled(!self.is_busy(), egui::Button::new("\u{2139} Show SAT Reduction"));
 
 
                 egui::Popup::menu(&sat_btn)
                         .close_behavior(egui::PopupCloseBehavior::CloseOnClickOutside)
                         .show(|ui| {
-                            ui.label(format!("SAT DIMACS CNF Form"));
+                            ui.label("SAT DIMACS CNF Form");
+
+                            ui.horizontal(|ui| {
+                                ui.selectable_value(&mut self.sat_summary, false, "Raw");
+                                ui.selectable_value(&mut self.sat_summary, true, "Summary");
+                            });
+
+                            ui.horizontal(|ui| {
+                                // The whole formula, even when the view below only shows the summary.
+                                if ui.button("\u{1F4CB} Copy CNF").clicked() {
+                                    ctx.copy_text(sudoku::get_sat_decode_with(&self.matrix, &self.constraints));
+                                    self.cnf_export_msg = Some("Copied to the clipboard".to_string());
+                                }
+
+                                if sudoku::sat_clause_count(&self.matrix) + self.constraints.clause_count(self.matrix.len()) > self.sat_display_limit
+                                    && ui.button("\u{1F4BE} Export CNF to file").clicked() {
+                                    let path = self.export_path("sudoku.cnf");
+                                    self.cnf_export_msg = Some(match sudoku::export_dimacs(&self.matrix, &self.constraints, &path) {
+                                        Ok(()) => format!("Saved to {}", path.display()),
+                                        Err(err) => format!("Export failed: {}", err),
+                                    });
+                                }
+
+                                if ui.button("\u{1F4BE} Export graph").on_hover_text("Cells and their peers, as a Graphviz DOT file").clicked() {
+                                    let path = self.export_path("sudoku.dot");
+                                    self.cnf_export_msg = Some(match std::fs::write(&path, sudoku::to_dot(&self.matrix)) {
+                                        Ok(()) => format!("Saved to {}", path.display()),
+                                        Err(err) => format!("Export failed: {}", err),
+                                    });
+                                }
+                            });
+
+                            if let Some(msg) = &self.cnf_export_msg {
+                                ui.label(msg);
+                            }
 
                             egui::ScrollArea::vertical()
                                 .auto_shrink([false, false])
                                 .stick_to_bottom(true)
                                 .show(ui, |ui| {
+                                    let text = if self.sat_summary {
+                                        // The summary builds the whole formula, so only once per board and rules.
+                                        match &self.sat_summary_text {
+                                            Some((board, rules, text)) if *board == self.matrix && *rules == self.constraints => text.clone(),
+                                            _ => {
+                                                let text = sudoku::get_sat_summary(&self.matrix, &self.constraints);
+                                                self.sat_summary_text = Some((self.matrix.clone(), self.constraints.clone(), text.clone()));
+                                                text
+                                            }
+                                        }
+                                    } else {
+                                        sudoku::get_sat_decode_bounded(&self.matrix, &self.constraints, self.sat_display_limit)
+                                    };
+
                                     ui.add(
                                         
                                     egui::Label::new(
-                                            egui::RichText::new(sudoku::get_sat_decode(&mut self.matrix))
+                                            egui::RichText::new(text)
                                                 //.size(14.0)
                                                 .strong()
                                                 .monospace()
@@ -196,8 +1600,49 @@ impl App for MatrixApp {
 
                 ui.add_space(10.);
 
-                if ui.add_enabled(self.rx_matrix.is_none(), egui::Button::new("\u{2705} Check Solution")).clicked() {
-                    let invalid_positions = sudoku::is_matrix_valid(&mut self.matrix);
+                egui::CollapsingHeader::new("Compare SAT encodings").show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Clues:");
+                        for clues in sudoku::ClueEncoding::ALL {
+                            ui.selectable_value(&mut self.clue_encoding, clues, clues.to_string())
+                                .on_hover_text("Also used when solving with SAT");
+                        }
+                    });
+
+                    egui::Grid::new("encodings_grid").striped(true).show(ui, |ui| {
+                        ui.strong("Encoding");
+                        ui.strong("Variables");
+                        ui.strong("Clauses");
+                        ui.end_row();
+
+                        // Counting means building every formula, so only redo it when the board or the rules change.
+                        let constraints = self.solving_constraints();
+                        let stale = !matches!(&self.encoding_stats,
+                            Some((board, rules, clues, _)) if *board == self.matrix && *rules == constraints && *clues == self.clue_encoding);
+                        if stale {
+                            let stats = sudoku::Encoding::ALL.iter().map(|encoding| encoding.stats_with(&self.matrix, &constraints, self.clue_encoding)).collect();
+                            self.encoding_stats = Some((self.matrix.clone(), constraints, self.clue_encoding, stats));
+                        }
+
+                        let Some((_, _, _, all_stats)) = &self.encoding_stats else {return};
+                        for (encoding, stats) in sudoku::Encoding::ALL.iter().zip(all_stats) {
+                            ui.label(encoding.to_string());
+                            ui.label(stats.variables.to_string());
+                            ui.label(stats.clauses.to_string());
+                            ui.end_row();
+                        }
+                    });
+                });
+
+                egui::CollapsingHeader::new("Puzzle analysis").show(ui, |ui| {
+                    ui.label(format!("Clues: {}", sudoku::clue_count(&self.matrix)));
+                    ui.label(format!("Empty cells: {}", sudoku::empty_count(&self.matrix)));
+                    ui.label(format!("Clue distribution: {:.2}", sudoku::clue_distribution_score(&self.matrix)))
+                        .on_hover_text("1 when rows, columns and blocks all hold as many clues, lower when clues are clustered");
+                });
+
+                if ui.add_enabled(!self.is_busy(), egui::Button::new("\u{2705} Check Solution")).clicked() {
+                    let invalid_positions = sudoku::is_matrix_valid_with(&self.matrix, &self.constraints);
 
                     self.invalid_poss = invalid_positions.clone();
                     self.show_correctness = true;
@@ -213,6 +1658,87 @@ impl App for MatrixApp {
                     }
                 }
 
+                ui.add_space(10.);
+
+                if ui.add_enabled(!self.is_busy(), egui::Button::new("\u{1F50D} Find Redundant Clues")).clicked() {
+                    let (tx, rx) = mpsc::channel::<Vec<(usize, usize)>>();
+                    let matrix_clone = self.matrix.clone();
+                    let constraints = self.solving_constraints();
+
+                    // One uniqueness check per clue: can take a while on big boards.
+                    self.submit(move |_| {
+                        tx.send(sudoku::redundant_clues_with(&matrix_clone, &constraints)).unwrap();
+                    });
+
+                    self.rx_redundant = Some(rx);
+                }
+
+                ui.add_space(10.);
+
+                if ui.add_enabled(!self.is_busy(), egui::Button::new("\u{1F575} Find Suspicious Cells")).clicked() {
+                    let (tx, rx) = mpsc::channel::<Vec<(usize, usize)>>();
+                    let matrix_clone = self.matrix.clone();
+                    let constraints = self.solving_constraints();
+
+                    self.submit(move |_| {
+                        tx.send(sudoku::find_unsatisfiable_core_with(&matrix_clone, &constraints)).unwrap();
+                    });
+
+                    self.suspicious_msg = None;
+                    self.rx_suspicious = Some(rx);
+                }
+
+                if let Some(msg) = &self.suspicious_msg {
+                    ui.label(msg);
+                }
+
+                ui.add_space(10.);
+
+                if self.browser.is_none() && ui.add_enabled(!self.is_busy(), egui::Button::new("\u{1F4D6} Browse Solutions")).clicked() {
+                    let (tx, rx) = mpsc::channel::<Vec<Vec<Vec<i8>>>>();
+                    let matrix_clone = self.matrix.clone();
+                    let constraints = self.solving_constraints();
+
+                    self.submit(move |_| {
+                        tx.send(sudoku::collect_solutions_with(&matrix_clone, &constraints, MAX_BROWSED_SOLUTIONS)).unwrap();
+                    });
+
+                    self.rx_solutions = Some(rx);
+                }
+
+                let mut close_browser = false;
+
+                if let Some(browser) = &mut self.browser {
+                    let shown = browser.index;
+
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(browser.index > 0, egui::Button::new("\u{25C0}")).clicked() {
+                            browser.index -= 1;
+                        }
+
+                        // Enumeration stops at the cap, so there may be more.
+                        let total = browser.solutions.len();
+                        ui.label(format!("Solution {} of {}{}", browser.index + 1, total, if total == MAX_BROWSED_SOLUTIONS {"+"} else {""}));
+
+                        if ui.add_enabled(browser.index + 1 < total, egui::Button::new("\u{25B6}")).clicked() {
+                            browser.index += 1;
+                        }
+
+                        close_browser = ui.button("Close").clicked();
+                    });
+
+                    if browser.index != shown {
+                        sudoku::update_origins(&mut self.origins, &self.matrix, &browser.solutions[browser.index], sudoku::CellOrigin::Solver);
+                        self.matrix = browser.solutions[browser.index].clone();
+                    }
+                }
+
+                // Only solver cells differ from the puzzle, and they all go back to blank.
+                if close_browser && let Some(browser) = self.browser.take() {
+                    sudoku::update_origins(&mut self.origins, &self.matrix, &browser.puzzle, sudoku::CellOrigin::Empty);
+                    self.matrix = browser.puzzle;
+                }
+
                 ui.add_space(5.);
 
                 if self.show_correctness {
@@ -250,96 +1776,206 @@ impl App for MatrixApp {
 
                 ui.add_space(10.);
 
+                // Can't happen with the size slider, but a bad (or imported, oversized) board must not reach the solvers.
+                let size_error = sudoku::assert_board_within(&self.matrix, self.max_matrix_size.pow(2)).err();
+                if let Some(err) = size_error {
+                    ui.label(egui::RichText::new(format!("Solving disabled: {}", err)).color(ui.visuals().error_fg_color));
+                }
+                let can_solve = !self.is_busy() && size_error.is_none();
 
-                if ui.add_enabled(self.rx_matrix.is_none(), egui::Button::new("\u{26A1} Solve Backtrack")).clicked()  {
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(can_solve, egui::Button::new("\u{26A1} Solve")).clicked() {
+                        self.start_solve(self.default_algorithm, false);
+                    }
 
-                    // Creating a message channel for non-blocking matrix receive.
-                    let (tx_matrix, rx_matrix) = mpsc::channel::<Vec<Vec<i8>>>();
+                    egui::ComboBox::from_label("Default solver")
+                    .selected_text(self.default_algorithm.to_string())
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.default_algorithm, sudoku::Algorithm::Backtracking, "Backtracking");
+                        ui.selectable_value(&mut self.default_algorithm, sudoku::Algorithm::Sat, "SAT");
+                        ui.selectable_value(&mut self.default_algorithm, sudoku::Algorithm::Auto, "Auto (Backtracking \u{2192} SAT)");
+                        ui.selectable_value(&mut self.default_algorithm, sudoku::Algorithm::Dlx, "DLX");
+                    });
+                });
 
-                    // Creating another message channel for non-blocking time receive.
-                    let (tx_time, rx_time) = mpsc::channel::<f64>();
+                ui.add_enabled(!self.is_busy(), egui::Checkbox::new(&mut self.respect_pencil_marks, "Respect pencil marks"))
+                    .on_hover_text("Marked cells may only take one of their marks");
 
-                    // Cloning self data since borrowing would escape from the method (error from compiler).
-                    let mut matrix_clone = self.matrix.clone();
- 
-                    // Execute algorithm on a separate thread (still sequentially)
-                    // This is needed to avoid GUI freezes for long computations.
-                    std::thread::spawn(move || {
-                        tx_time.send(sudoku::solve_backtracking_time(&mut matrix_clone)).unwrap();
-                        tx_matrix.send(matrix_clone).unwrap();
+                if ui.add_enabled(can_solve, egui::Button::new("\u{1F6E1} Validate & Solve")).on_hover_text("Solves only if no clue breaks a rule").clicked() {
+                    self.start_solve(self.default_algorithm, true);
+                }
+
+                if let Some(msg) = &self.validation_msg {
+                    ui.label(msg);
+                }
+
+                if self.default_algorithm == sudoku::Algorithm::Auto {
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut self.fallback_steps).range(1_000..=100_000_000).speed(1_000));
+                        ui.label("Backtracking steps before SAT");
                     });
- 
-                    self.rx_matrix = Some(rx_matrix);
-                    self.rx_time = Some(rx_time);
+                }
+
+                ui.add_space(10.);
+
+                if ui.add_enabled(can_solve, egui::Button::new("\u{26A1} Solve Backtrack")).clicked()  {
+                    self.start_solve(sudoku::Algorithm::Backtracking, false);
+                }
+
+                if self.estimate.as_ref().is_none_or(|(board, _)| *board != self.matrix) {
+                    self.estimate = Some((self.matrix.clone(), sudoku::estimate_difficulty_fast(&self.matrix)));
+                }
 
+                if let Some((_, estimate)) = self.estimate {
+                    ui.label(format!("Backtracking: {}", estimate));
                 }
 
                 ui.add_space(10.);
 
-                if ui.add_enabled(self.rx_matrix.is_none(), egui::Button::new("\u{26A1} Solve SAT")).clicked()  {
+                if ui.add_enabled(can_solve, egui::Button::new("\u{26A1} Solve SAT")).clicked()  {
+                    self.start_solve(sudoku::Algorithm::Sat, false);
+                }
 
-                    // Creating a message channel for non-blocking matrix receive.
-                   let (tx_matrix, rx_matrix) = mpsc::channel::<Vec<Vec<i8>>>();
+                ui.add_space(10.);
 
-                    // Creating another message channel for non-blocking time receive.
-                    let (tx_time, rx_time): (mpsc::Sender<f64>, mpsc::Receiver<f64>) = mpsc::channel();
+                if ui.add_enabled(can_solve, egui::Button::new("\u{26A1} Solve DLX")).clicked()  {
+                    self.start_solve(sudoku::Algorithm::Dlx, false);
+                }
 
-                    // Cloning self data since borrowing would escape from the method (error from compiler).
-                    let mut matrix_clone = self.matrix.clone();
+                ui.add_space(10.);
 
-                    // Execute algorithm on a separate thread (still sequentially)
-                    // This is needed to avoid GUI freezes for long computations.
-                    std::thread::spawn(move || {
-                        tx_time.send(sudoku::solve_sat_time(&mut matrix_clone)).unwrap();
-                        tx_matrix.send(matrix_clone).unwrap();
+                // A full SAT solve of the board on the worker; only the block is copied back.
+                let selected_block = self.selected_cell.map(|pos| self.constraints.block_of(self.matrix.len(), pos));
+                if ui.add_enabled(can_solve && selected_block.is_some(), egui::Button::new("\u{26A1} Solve Block"))
+                    .on_hover_text("Fills only the box (or jigsaw region) of the selected cell")
+                    .clicked()
+                    && let Some(block) = selected_block {
+                    let (tx, rx) = mpsc::channel::<Option<Vec<Vec<i8>>>>();
+                    let (matrix, constraints) = (self.matrix.clone(), self.solving_constraints());
+
+                    self.submit(move |_| {
+                        tx.send(sudoku::solve_region(&matrix, &constraints, block)).unwrap();
                     });
 
-                    self.rx_matrix = Some(rx_matrix);
-                    self.rx_time = Some(rx_time);
+                    self.rx_region = Some(rx);
+                }
+
+                ui.add_space(10.);
+
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(can_solve, egui::Button::new("\u{1F3AC} Animate Backtrack")).clicked() {
+                        self.stepper = Some(sudoku::BacktrackStepper::new(sudoku::Board::from_matrix(&self.matrix), self.solving_constraints()));
+                        self.animation_paused = false;
+                        self.last_step = Instant::now();
+                        self.solution_time = f64::NAN;
+                        self.solved_by = None;
+                    }
+
+                    if self.stepper.is_some() {
+                        if ui.button(if self.animation_paused {"\u{25B6} Play"} else {"\u{23F8} Pause"}).clicked() {
+                            self.animation_paused = !self.animation_paused;
+                        }
+
+                        if ui.button("\u{23F9} Stop").clicked() {
+                            self.stop_animation();
+                        }
+                    }
+                });
 
-               }
+                ui.add(egui::Slider::new(&mut self.step_delay_ms, 0..=500).text("Step delay (ms)"));
 
                 ui.add_space(5.);
 
                  if !self.solution_time.is_nan() {
 
+                    let result_text = match self.solved_by {
+                        _ if !self.solution_time.is_finite() && self.respect_pencil_marks && !self.pencil_marks.is_empty() =>
+                            "\u{274C} Puzzle is unsolvable with these pencil marks.".to_string(),
+                        _ if !self.solution_time.is_finite() => "\u{274C} Puzzle is unsolvable.".to_string(),
+                        Some(solved_by) => format!("Solution found in {} ({}).", sudoku::format_seconds(self.solution_time), solved_by),
+                        None => format!("Solution found in {}.", sudoku::format_seconds(self.solution_time)),
+                    };
+
                     ui.label(
-                        egui::RichText::new(if self.solution_time.is_finite() {format!("Solution found in {:.3} s.", self.solution_time)} else {"\u{274C} Puzzle is unsolvable.".to_string()})
+                        egui::RichText::new(result_text)
                             .size(14.0)
                             .strong()
                             .color(if self.solution_time.is_finite() {egui::Color32::DARK_GREEN} else {egui::Color32::DARK_RED})
                             .monospace()
                     );
-                }
 
-                ui.separator();
+                    match self.best_outcome {
+                        Some(history::BestOutcome::NewBest) => {
+                            ui.label(egui::RichText::new("\u{1F3C6} New best!").strong().color(egui::Color32::GOLD));
+                        }
+                        Some(history::BestOutcome::Previous(best)) => {
+                            ui.label(format!("Previous best: {}", sudoku::format_seconds(best)));
+                        }
+                        None => {}
+                    }
 
-                ui.add_space(10.);
-                                
-                if self.rx_matrix.is_some(){
-                    ui.spinner();
+                    match &self.reference_check {
+                        Some(sudoku::ReferenceCheck::Matches) => {
+                            ui.colored_label(egui::Color32::DARK_GREEN, "\u{2714} Matches the reference solution.");
+                        }
+                        Some(sudoku::ReferenceCheck::OtherSolution) => {
+                            ui.label("\u{2714} Valid, the puzzle has several solutions besides the reference.");
+                        }
+                        Some(sudoku::ReferenceCheck::Mismatch(cells)) => {
+                            ui.colored_label(ui.visuals().warn_fg_color, format!("\u{26A0} {} cell(s) differ from the reference of a unique puzzle.", cells.len()));
+                        }
+                        Some(sudoku::ReferenceCheck::Invalid(cells)) => {
+                            ui.colored_label(ui.visuals().error_fg_color, format!("\u{274C} Output breaks the rules in {} cell(s).", cells.len()));
+                        }
+                        None => {}
+                    }
+                }
+
+                if ui.add_enabled(!self.best_times.is_empty(), egui::Button::new("Reset Best Times")).clicked() {
+                    self.best_times.clear();
+                    self.best_outcome = None;
                 }
 
+                if !self.solve_history.is_empty() {
+                    egui::CollapsingHeader::new("Solve time history").show(ui, |ui| {
+                        // One series per solver, so the legend tells them apart. Unsolvable attempts have no bar.
+                        let mut series: Vec<(String, Vec<egui_plot::Bar>)> = Vec::new();
 
+                        for (i, record) in self.solve_history.records().enumerate() {
+                            if !record.seconds.is_finite() {continue}
 
+                            let name = record.solved_by.map_or(record.algorithm.to_string(), |solved_by| solved_by.to_string());
+                            let bar = egui_plot::Bar::new(i as f64 + 1., record.seconds * 1000.).width(0.8);
 
+                            match series.iter_mut().find(|(n, _)| *n == name) {
+                                Some((_, bars)) => bars.push(bar),
+                                None => series.push((name, vec![bar])),
+                            }
+                        }
 
-                // Check completition (if there is any) with non-blocking receive
-                if let Some(rx) = &self.rx_matrix {
-                    if let Ok(new_matrix) = rx.try_recv() {
-                        self.matrix = new_matrix;
-                        println!("Received computation.");
-                        self.rx_matrix = None;
-                    }
+                        egui_plot::Plot::new("solve_history_plot")
+                            .height(150.)
+                            .legend(egui_plot::Legend::default())
+                            .x_axis_label("Solve")
+                            .y_axis_label("ms")
+                            .show(ui, |plot_ui| {
+                                for (name, bars) in series {
+                                    plot_ui.bar_chart(egui_plot::BarChart::new(name, bars));
+                                }
+                            });
+                    });
                 }
 
-                // Check completition (if there is any) with non-blocking receive
-                if let Some(rx) = &self.rx_time {
-                    if let Ok(elap_time) = rx.try_recv() {
-                        self.solution_time = elap_time;
-                        println!("Received time.");
-                        self.rx_time = None;
-                    }
+                ui.separator();
+
+                ui.add_space(10.);
+                                
+                if self.is_computing() {
+                    ui.spinner();
+                }
+
+                if let Some(msg) = &self.job_error {
+                    ui.colored_label(ui.visuals().error_fg_color, msg);
                 }
 
             });
@@ -348,94 +1984,358 @@ impl App for MatrixApp {
 
         egui::CentralPanel::default().show(ctx, |ui| {
 
-            ui.label(
-                egui::RichText::new("Sudoku Grid")
-                    .size(20.0)
-                    .strong()
-                    .monospace()
-            );
+            let presenting = self.presentation_mode;
+
+            if !presenting {
+                ui.label(
+                    egui::RichText::new("Sudoku Grid")
+                        .size(20.0)
+                        .strong()
+                        .monospace()
+                );
+            }
+
+            if self.warn_dead_ends && !presenting && !self.is_busy() && self.matrix.len() <= DEAD_END_CHECK_MAX_SIZE {
+                self.schedule_dead_end_check(ctx);
+
+                // A verdict on an older board is kept quiet until the current one is checked.
+                if let Some((board, _, false)) = &self.completable && *board == self.matrix {
+                    ui.colored_label(ui.visuals().error_fg_color, "\u{26A0} This state is unsolvable, undo needed.");
+                }
+            }
             
             ui.add_space(25.);
 
 
+            // Cells of a generated puzzle still being dealt are drawn blank, repainting until the last one shows.
+            let dealing: Vec<(usize, usize)> = self.deal.as_ref().map(|deal| deal.hidden().to_vec()).unwrap_or_default();
+            if self.deal.as_ref().is_some_and(deal::Deal::is_done) {
+                self.deal = None;
+            } else if self.deal.is_some() {
+                ctx.request_repaint();
+            }
+
+            // Empty peers of the hovered filled cell, where its value is therefore forbidden.
+            let forbidden_cells: Vec<(usize, usize)> = match self.hovered_cell {
+                Some((row, col)) if self.matrix[row][col] != 0 => sudoku::affected_cells(self.matrix.len(), (row, col), &self.constraints)
+                    .into_iter()
+                    .filter(|&(r, c)| self.matrix[r][c] == 0)
+                    .collect(),
+                _ => Vec::new(),
+            };
+            let easy_moves: Vec<(usize, usize)> = if self.show_easy_moves && self.constraints.regions.is_none() && !presenting {
+                sudoku::last_in_unit_cells(&self.matrix).into_iter().map(|(row, col, _)| (row, col)).collect()
+            } else {
+                Vec::new()
+            };
+            let mut hovered_cell = None;
+            let grid_scale = self.grid_scale;
+            let cell_margin = |margin: f32| (margin * grid_scale).round() as i8;
+
+            // Edits would be silently overwritten by the incoming result, so the grid is read-only meanwhile.
+            let locked = self.is_busy();
+
             /*
-                Vertical alignment not working as expected for Grids.
-                Using ScrollArea in case of overflowing content.
-                ScrollArea does not show otherwise by default.
+                Layouts can't center a Grid (its size is only known once drawn), so it is padded by the free space
+                around the size it had on the previous frame. The padded content never exceeds the panel,
+                so the ScrollArea only shows scrollbars when the grid alone doesn't fit.
              */
-            egui::ScrollArea::both().show(ui,|ui| {
-
-                // Draw the matrix with a grid and borders
-                egui::Grid::new("matrix_grid")
-                    //.striped(true)
-                    .spacing([4., 4.])
-                    .show(ui, |ui| {
-                        // Cycle by index and not by value to avoid borrowing issues
-                        for row_index in 0..self.matrix_size.pow(2) {
-                            for col_index in 0..self.matrix_size.pow(2) {
-                        //for (row_index, row) in &mut self.matrix.iter().enumerate() {
-                          //  for (col_index, value) in row.iter().enumerate() {
-                                
-                                ui.push_id((row_index, col_index), |ui| {
-
-                                    let resp = ui.interact(ui.max_rect(), ui.id(), egui::Sense::click());
-
-                                    // Draw each cell with a border
-                                    ui.vertical_centered(|ui| {
-                                        egui::Frame::new()
-                                        // Integer quotient represents block group. % 2 alternates each group.
-                                        .fill(if (row_index / self.matrix_size) % 2 == (col_index / self.matrix_size) % 2  {ui.visuals().warn_fg_color} else {ui.visuals().widgets.inactive.bg_fill})
-                                        .stroke(egui::Stroke::new(
-                                            2.0,
-                                            if resp.hovered()
-                                                {ui.visuals().widgets.active.bg_stroke.color} else {egui::Color32::TRANSPARENT}))
-                                        .inner_margin(egui::Margin {
-                                            left: 8,
-                                            right: 8,
-                                            top: 10,
-                                            bottom: 10})
-                                        .show(ui, |ui|{
-                                            let value = self.matrix[row_index][col_index];
-                                            ui.add(egui::Label::new(
-                                                egui::RichText::new(if value > 0 {format!("{}", value)} else {String::from(" ")}) 
-                                                .color(if self.invalid_poss.contains(&(row_index, col_index)) {ui.visuals().error_fg_color} else {ui.visuals().strong_text_color()})
-                                                .size(16.0)
-                                                .strong()
-                                            ).selectable(false))
-                                        });
+            let padding = ((ui.available_size() - self.grid_size - ui.spacing().item_spacing) / 2.).max(egui::Vec2::ZERO).floor();
 
-                                        let popup_id = ui.make_persistent_id("edit_popup");
-                                        
-                                        if resp.secondary_clicked() {
-                                            //ui.memory_mut(|mem| mem.open_popup(popup_id));
-                                            egui::Popup::open_id(ctx, popup_id);       
-                                        }
+            let grid_area = egui::ScrollArea::both().show(ui,|ui| {
+
+                if locked {
+                    ui.disable();
+                }
+
+                ui.add_space(padding.y);
+
+                ui.horizontal(|ui| {
+                    ui.add_space(padding.x);
+
+                    // Draw the matrix with a grid and borders
+                    let grid = egui::Grid::new("matrix_grid")
+                        //.striped(true)
+                        .spacing([4. * grid_scale, 4. * grid_scale])
+                        .show(ui, |ui| {
+                            // Headers take their own grid row/column, shading keeps using the cell indices.
+                            if self.show_coordinates {
+                                ui.label("");
+                                for col_index in 0..self.matrix_size.pow(2) {
+                                    ui.vertical_centered(|ui| ui.label(egui::RichText::new(column_label(col_index)).monospace().size(14. * grid_scale)));
+                                }
+                                ui.end_row();
+                            }
+
+                            // Cycle by index and not by value to avoid borrowing issues
+                            for row_index in 0..self.matrix_size.pow(2) {
+                                if self.show_coordinates {
+                                    ui.label(egui::RichText::new(format!("{}", row_index + 1)).monospace().size(14. * grid_scale));
+                                }
+
+                                for col_index in 0..self.matrix_size.pow(2) {
+                            //for (row_index, row) in &mut self.matrix.iter().enumerate() {
+                              //  for (col_index, value) in row.iter().enumerate() {
+                                
+                                    ui.push_id((row_index, col_index), |ui| {
 
-                                        egui::Popup::menu(&resp)
-                                            .id(popup_id)
-                                            .close_behavior(egui::PopupCloseBehavior::CloseOnClickOutside)
-                                            .show(|ui| {
-                                                //println!("Popup ID: {:?}", popup_id);
-                                                ui.label(format!("Changing value of ({}, {})", row_index, col_index));
+                                        let resp = ui.interact(ui.max_rect(), ui.id(), cell_sense(presenting));
 
+                                        if resp.hovered() && !presenting {
+                                            hovered_cell = Some((row_index, col_index));
+                                        }
 
-                                                ui.add(egui::Slider::new(&mut self.matrix[row_index][col_index], 0..=self.matrix_size.pow(2) as i8));
+                                        if resp.clicked() {
+                                            self.selected_cell = Some((row_index, col_index));
+                                            self.deal = None;
+                                        }
 
-                                                // Disable solution check colors
-                                                self.show_correctness = false;
-                                                self.invalid_poss.clear();
+                                        // Draw each cell with a border
+                                        ui.vertical_centered(|ui| {
+                                            egui::Frame::new()
+                                            // Integer quotient represents block group. % 2 alternates each group.
+                                            .fill(if forbidden_cells.contains(&(row_index, col_index)) {ui.visuals().selection.bg_fill}
+                                            else if easy_moves.contains(&(row_index, col_index)) {SUGGESTED_COLOR.gamma_multiply(0.5)}
+                                            else if let Some(regions) = &self.constraints.regions {region_color(regions.region_of((row_index, col_index)), regions.size(), self.dark_mode)}
+                                                else if (row_index / self.matrix_size) % 2 == (col_index / self.matrix_size) % 2  {ui.visuals().warn_fg_color} else {ui.visuals().widgets.inactive.bg_fill})
+                                            // The selection cursor stands out from the hover outline.
+                                            .stroke(if presenting {egui::Stroke::new(2.0, egui::Color32::TRANSPARENT)}
+                                                else if self.selected_cell == Some((row_index, col_index)) {egui::Stroke::new(2.0, ui.visuals().selection.stroke.color)}
+                                                else if resp.hovered() {egui::Stroke::new(2.0, ui.visuals().widgets.active.bg_stroke.color)}
+                                                else {egui::Stroke::new(2.0, egui::Color32::TRANSPARENT)})
+                                            .inner_margin(egui::Margin {
+                                                left: cell_margin(8.),
+                                                right: cell_margin(8.),
+                                                top: cell_margin(10.),
+                                                bottom: cell_margin(10.)})
+                                            .show(ui, |ui|{
+                                                let value = if dealing.contains(&(row_index, col_index)) {0}
+                                                    else if let Some(solution) = &self.presentation_board {solution[row_index][col_index]}
+                                                    else {self.matrix[row_index][col_index]};
+
+                                                if value == 0 && let Some(marks) = self.pencil_marks.get(&(row_index, col_index)) {
+                                                    let marks: Vec<String> = marks.iter().map(|v| v.to_string()).collect();
+                                                    ui.add(egui::Label::new(
+                                                        egui::RichText::new(marks.join(""))
+                                                        .color(ui.visuals().weak_text_color())
+                                                        .size(9.0 * grid_scale)
+                                                    ).selectable(false))
+                                                } else {
+                                                    // Clues in bold, then one color per origin unless a check highlights the cell.
+                                                    let origin = self.origins[row_index][col_index];
+                                                    let text = egui::RichText::new(if value > 0 {format!("{}", value)} else {self.blank_style.glyph().to_string()})
+                                                        .color(if self.invalid_poss.contains(&(row_index, col_index)) {ui.visuals().error_fg_color}
+                                                            else if self.suspicious_cells.contains(&(row_index, col_index)) {SUSPICIOUS_COLOR}
+                                                            else if self.redundant_clues.contains(&(row_index, col_index)) {ui.visuals().hyperlink_color}
+                                                            else if self.changed_cells.contains(&(row_index, col_index)) {egui::Color32::ORANGE}
+                                                            else if self.suggested_cells.contains(&(row_index, col_index)) {SUGGESTED_COLOR}
+                                                            else if origin == sudoku::CellOrigin::Player {PLAYER_COLOR}
+                                                            else if origin == sudoku::CellOrigin::Solver {SOLVER_COLOR}
+                                                            else {ui.visuals().strong_text_color()})
+                                                        .size(16.0 * grid_scale);
+
+                                                    ui.add(egui::Label::new(
+                                                        if origin == sudoku::CellOrigin::Given {text.strong()} else {text}
+                                                    ).selectable(false))
+                                                }
                                             });
 
+                                            // Keyed on the coordinates alone so every cell owns its popup, whatever the widget nesting.
+                                            let popup_id = egui::Id::new(("edit_popup", row_index, col_index));
+                                        
+                                            if locked || presenting {
+                                                egui::Popup::close_id(ctx, popup_id);
+                                            } else if resp.secondary_clicked() {
+                                                //ui.memory_mut(|mem| mem.open_popup(popup_id));
+                                                egui::Popup::open_id(ctx, popup_id);       
+                                            }
+
+                                            egui::Popup::menu(&resp)
+                                                .id(popup_id)
+                                                .close_behavior(egui::PopupCloseBehavior::CloseOnClickOutside)
+                                                .show(|ui| {
+                                                    //println!("Popup ID: {:?}", popup_id);
+                                                    ui.label(format!("Changing value of ({}, {})", row_index, col_index));
+
+                                                    let hidden_singles = sudoku::hidden_singles_at(&self.matrix, (row_index, col_index), &self.constraints);
+                                                    if !hidden_singles.is_empty() {
+                                                        let values: Vec<String> = hidden_singles.iter().map(|v| v.to_string()).collect();
+                                                        ui.label(egui::RichText::new(format!("Only spot for: {}", values.join(", "))).color(ui.visuals().hyperlink_color))
+                                                            .on_hover_text("No other cell of this row, column or block can hold these values");
+                                                    }
+
+                                                    // Only a warning: conflicting givens are allowed while building a puzzle.
+                                                    let conflicts = sudoku::conflicting_values_with(&self.matrix, (row_index, col_index), &self.constraints);
+                                                    if !conflicts.is_empty() {
+                                                        let values: Vec<String> = conflicts.iter().map(|v| v.to_string()).collect();
+                                                        ui.label(format!("Conflicting values: {}", values.join(", ")));
+
+                                                        let current = self.matrix[row_index][col_index];
+                                                        if conflicts.contains(&current) {
+                                                            ui.label(egui::RichText::new(format!("\u{26A0} {} breaks a rule here", current)).color(ui.visuals().warn_fg_color));
+                                                        }
+                                                    }
+
+                                                    let prev_value = self.matrix[row_index][col_index];
+                                                    if ui.add(egui::Slider::new(&mut self.matrix[row_index][col_index], 0..=self.matrix_size.pow(2) as i8)).changed() {
+                                                        self.record_move(row_index, col_index, prev_value);
+                                                        self.origins[row_index][col_index] = sudoku::CellOrigin::for_value(self.matrix[row_index][col_index], sudoku::CellOrigin::Player);
+                                                    }
+
+                                                    // Toggling every mark off removes the restriction.
+                                                    ui.label("Pencil marks:");
+                                                    ui.horizontal_wrapped(|ui| {
+                                                        let mut marks = self.pencil_marks.get(&(row_index, col_index)).cloned().unwrap_or_default();
+                                                        for value in 1..=self.matrix_size.pow(2) as i8 {
+                                                            let mut marked = marks.contains(&value);
+                                                            if ui.toggle_value(&mut marked, value.to_string()).changed() {
+                                                                marks.retain(|&v| v != value);
+                                                                if marked {marks.push(value)}
+                                                                marks.sort();
+
+                                                                if marks.is_empty() {
+                                                                    self.pencil_marks.remove(&(row_index, col_index));
+                                                                } else {
+                                                                    self.pencil_marks.insert((row_index, col_index), marks.clone());
+                                                                }
+                                                            }
+                                                        }
+                                                    });
+
+                                                    // Disable solution check colors
+                                                    self.show_correctness = false;
+                                                    self.invalid_poss.clear();
+                                                    self.redundant_clues.clear();
+                                                    self.suspicious_cells.clear();
+                                                    self.suspicious_msg = None;
+                                                    self.changed_cells.clear();
+                                                });
+
 
+                                        });
                                     });
-                                });
+                                }
+                                ui.end_row();
+
                             }
-                            ui.end_row();
+                        });
 
-                        }
-                    });
+                    // Resizing the board or zooming changes the size: center again right away.
+                    if grid.response.rect.size() != self.grid_size {
+                        self.grid_size = grid.response.rect.size();
+                        ctx.request_repaint();
+                    }
+
+                    grid.response.rect
+                }).inner
 
             });
+
+            // The step animation is meant to be watched, only background computations get the overlay.
+            if self.is_computing() {
+                // Only over the visible part of the grid, not its padding.
+                let rect = grid_area.inner.intersect(grid_area.inner_rect);
+                let painter = ui.painter_at(rect);
+
+                painter.rect_filled(rect, 4., ui.visuals().extreme_bg_color.gamma_multiply(0.6));
+                painter.text(
+                    rect.center(),
+                    egui::Align2::CENTER_CENTER,
+                    if self.pending_solve || self.rx_region.is_some() {"Solving\u{2026}"} else {"Working\u{2026}"},
+                    egui::FontId::proportional(20.),
+                    ui.visuals().strong_text_color(),
+                );
+            }
+
+            // The highlight is drawn from last frame's hover: repaint once more when it moves (or leaves the grid).
+            if hovered_cell != self.hovered_cell {
+                self.hovered_cell = hovered_cell;
+                ctx.request_repaint();
+            }
         });
+
+        // A plain window rather than a modal: the grid stays usable while it is open.
+        egui::Window::new("About")
+            .open(&mut self.show_about)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(egui::RichText::new(format!("Sudoku Solver v{}", sudoku::VERSION)).size(16.).strong());
+                ui.add_space(5.);
+                ui.label(format!("SAT backend: {}", sudoku::SAT_BACKEND));
+                ui.label("Backtracking and SAT reduction solvers, built with egui.");
+                ui.add_space(5.);
+                ui.label("By Yuri Brandi, MIT licensed.");
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn column_labels_go_past_z() {
+        let labels: Vec<String> = [0, 8, 25, 26, 27, 51, 52, 701, 702].into_iter().map(column_label).collect();
+
+        assert_eq!(labels, ["A", "I", "Z", "AA", "AB", "AZ", "BA", "ZZ", "AAA"]);
+    }
+
+    #[test]
+    fn each_tick_advances_the_due_steps() {
+        assert_eq!(steps_per_tick(100, Duration::from_millis(50)), 0);
+        assert_eq!(steps_per_tick(100, Duration::from_millis(250)), 2);
+        assert_eq!(steps_per_tick(0, Duration::ZERO), FAST_FORWARD_STEPS);
+
+        let matrix = sudoku::parse_puzzle("530070000600195000098000060800060003400803001700020006060000280000419005000080079").unwrap();
+        let mut stepper = sudoku::BacktrackStepper::new(sudoku::Board::from_matrix(&matrix), sudoku::ConstraintSet::default());
+
+        assert_eq!(stepper.advance(steps_per_tick(10, Duration::from_millis(35))), 3);
+        assert_eq!(stepper.board().to_matrix().iter().flatten().filter(|&&v| v != 0).count(), sudoku::clue_count(&matrix) + 3);
+    }
+
+    #[test]
+    fn broken_icons_are_skipped() {
+        assert!(load_icon(b"not an image").is_none());
+        assert!(load_icon(&[]).is_none());
+        assert!(load_icon(include_bytes!("../assets/icon.png")).is_some());
+    }
+
+    #[test]
+    fn revert_restores_the_loaded_puzzle() {
+        let puzzle = sudoku::parse_puzzle("1204301221004301").unwrap();
+        let mut app = MatrixApp::with_storage(None);
+        app.load_puzzle(puzzle.clone()).unwrap();
+
+        app.matrix[0][2] = 3;
+        app.matrix[3][3] = 0;
+        app.revert_to_initial();
+
+        assert_eq!(app.matrix, puzzle);
+        assert_eq!(app.origins, sudoku::given_origins(&puzzle));
+        assert_eq!(app.matrix_size, 2);
+    }
+
+    #[test]
+    fn presentation_mode_ignores_cell_input() {
+        assert!(!cell_sense(true).senses_click());
+        assert!(cell_sense(false).senses_click());
+
+        let typing = |app: &mut MatrixApp| {
+            let input = egui::RawInput {events: vec![egui::Event::Text("4".to_string())], ..Default::default()};
+            let _ = egui::Context::default().run(input, |ctx| app.handle_keyboard_entry(ctx));
+        };
+
+        let mut app = MatrixApp::with_storage(None);
+        app.load_puzzle(sudoku::parse_puzzle("1204301221004301").unwrap()).unwrap();
+        app.selected_cell = Some((0, 2));
+
+        app.presentation_mode = true;
+        typing(&mut app);
+        assert_eq!(app.matrix[0][2], 0);
+
+        app.presentation_mode = false;
+        typing(&mut app);
+        assert_eq!(app.matrix[0][2], 4);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,139 @@
+use std::{cmp::Ordering, fs, io, path::Path};
+use serde::{Deserialize, Serialize};
+use sudoku::sudoku;
+
+/*
+    Everything needed to reproduce a solve: the board plus the solver and rules that were selected.
+    Saved as JSON, so a replay attached to a bug report can be read (and edited) by hand.
+*/
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Replay {
+    pub puzzle: String, // As produced by sudoku::export_puzzle_to_string
+    pub size: usize, // Side of the board, e.g. 9
+    pub algorithm: sudoku::Algorithm,
+    pub rules: Rules,
+    pub clues: sudoku::ClueEncoding, // How SAT encodes the clues
+    pub fallback_steps: usize,
+    pub seed: Option<u64>, // Set for seeded puzzles (e.g. the daily one), see sudoku::generate_seeded
+}
+
+impl Replay {
+    // The board, checked against the recorded size.
+    pub fn matrix(&self) -> io::Result<Vec<Vec<i8>>> {
+        let matrix = sudoku::parse_puzzle(&self.puzzle).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        if matrix.len() != self.size {
+            let msg = format!("puzzle is {}x{} but the replay says {}x{}", matrix.len(), matrix.len(), self.size, self.size);
+            return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+        }
+
+        Ok(matrix)
+    }
+}
+
+type Cell = (usize, usize); // (row, col)
+
+/*
+    The solving rules (sudoku::ConstraintSet) in a shape JSON can hold: cells are keys of the pencil marks
+    and inequalities use cmp::Ordering, neither of which serde_json writes as is.
+*/
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Rules {
+    pub variants: Vec<sudoku::Variant>,
+    pub inequalities: Vec<(Cell, Cell, char)>, // '<', '=' or '>' between the two cells' values
+    pub pencil_marks: Vec<(Cell, Vec<i8>)>, // Values each cell is restricted to
+    pub regions: Option<Vec<Vec<Cell>>>, // Cells of each jigsaw region
+}
+
+impl From<&sudoku::ConstraintSet> for Rules {
+    fn from(constraints: &sudoku::ConstraintSet) -> Self {
+        let symbol = |ord| match ord {
+            Ordering::Less => '<',
+            Ordering::Equal => '=',
+            Ordering::Greater => '>',
+        };
+
+        Self {
+            variants: constraints.variants.clone(),
+            inequalities: constraints.inequalities.iter().map(|&(a, b, ord)| (a, b, symbol(ord))).collect(),
+            pencil_marks: constraints.pencil_marks.iter().map(|(&pos, marks)| (pos, marks.clone())).collect(),
+            regions: constraints.regions.as_ref().map(|regions| regions.cells().to_vec()),
+        }
+    }
+}
+
+impl Rules {
+    // Back to a ConstraintSet, rejecting unknown inequality symbols and regions that don't partition a board.
+    pub fn to_constraints(&self) -> io::Result<sudoku::ConstraintSet> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+        let inequalities = self.inequalities.iter()
+            .map(|&(a, b, symbol)| match symbol {
+                '<' => Ok((a, b, Ordering::Less)),
+                '=' => Ok((a, b, Ordering::Equal)),
+                '>' => Ok((a, b, Ordering::Greater)),
+                _ => Err(invalid(format!("unknown inequality '{}'", symbol))),
+            })
+            .collect::<io::Result<Vec<sudoku::Inequality>>>()?;
+
+        let regions = self.regions.clone()
+            .map(|cells| sudoku::Regions::new(cells).map_err(|err| invalid(err.to_string())))
+            .transpose()?;
+
+        Ok(sudoku::ConstraintSet {
+            inequalities,
+            variants: self.variants.clone(),
+            pencil_marks: self.pencil_marks.iter().cloned().collect(),
+            regions,
+        })
+    }
+}
+
+pub fn to_json(replay: &Replay) -> String {
+    serde_json::to_string_pretty(replay).expect("Replay serialization err")
+}
+
+pub fn from_json(json: &str) -> Result<Replay, serde_json::Error> {
+    serde_json::from_str(json)
+}
+
+pub fn save_replay(replay: &Replay, path: &Path) -> io::Result<()> {
+    fs::write(path, to_json(replay))
+}
+
+pub fn load_replay(path: &Path) -> io::Result<Replay> {
+    let json = fs::read_to_string(path)?;
+    from_json(&json).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_round_trips_with_its_rules() {
+        let mut constraints = sudoku::ConstraintSet {
+            inequalities: vec![((0, 0), (0, 1), Ordering::Greater)],
+            regions: Some(sudoku::Regions::from_map("AAAB CABB CCDB CDDD").unwrap()),
+            ..Default::default()
+        };
+        constraints.set_variant(sudoku::Variant::AntiKing, true);
+        constraints.set_pencil_marks((3, 3), vec![1, 2]);
+
+        let replay = Replay {
+            puzzle: "1000003000020004".to_string(),
+            size: 4,
+            algorithm: sudoku::Algorithm::Sat,
+            rules: Rules::from(&constraints),
+            clues: sudoku::ClueEncoding::Full,
+            fallback_steps: 1000,
+            seed: Some(42),
+        };
+
+        let loaded = from_json(&to_json(&replay)).unwrap();
+
+        assert_eq!(loaded, replay);
+        assert_eq!(loaded.rules.to_constraints().unwrap(), constraints);
+        assert_eq!(loaded.matrix().unwrap().len(), 4);
+    }
+}
@@ -1,8 +1,40 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
 use std::time::Instant;
 use rand::{Rng, rng};
+use serde::{Deserialize, Serialize};
 use varisat::{CnfFormula, ExtendFormula, Lit, Solver, dimacs};
 
-pub fn solve_backtracking_time(matrix: &mut Vec<Vec<i8>>) -> f64 {
+mod techniques;
+mod generation;
+mod logging;
+mod dataset;
+mod symmetry;
+mod formats;
+mod dlx;
+mod regions;
+mod samurai;
+pub mod ocr;
+pub mod qr;
+pub mod pdf;
+#[cfg(feature = "serve")]
+pub mod server;
+
+pub use techniques::*;
+pub use generation::*;
+pub use dataset::*;
+pub use symmetry::*;
+pub use formats::*;
+pub use dlx::solve_dlx;
+pub use regions::*;
+pub use samurai::*;
+pub use logging::{SOLVER_LOG_ENV, SolverLog};
+
+pub fn solve_backtracking_time(matrix: &mut [Vec<i8>]) -> f64 {
 
     let start = Instant::now();
 
@@ -13,7 +45,7 @@ pub fn solve_backtracking_time(matrix: &mut Vec<Vec<i8>>) -> f64 {
     f64::INFINITY
 }
 
-pub fn solve_sat_time(matrix: &mut Vec<Vec<i8>>) -> f64 {
+pub fn solve_sat_time(matrix: &mut [Vec<i8>]) -> f64 {
 
     let start = Instant::now();
 
@@ -24,52 +56,305 @@ pub fn solve_sat_time(matrix: &mut Vec<Vec<i8>>) -> f64 {
     f64::INFINITY
 }
 
-pub fn get_sat_decode(matrix: &mut Vec<Vec<i8>>) -> String {
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Algorithm {
+    Backtracking,
+    #[default]
+    Sat,
+    Auto, // Backtracking, falling back to SAT when it takes too long
+    Dlx,  // Exact cover with Dancing Links
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Algorithm::Backtracking => write!(f, "Backtracking"),
+            Algorithm::Sat => write!(f, "SAT"),
+            Algorithm::Auto => write!(f, "Auto"),
+            Algorithm::Dlx => write!(f, "DLX"),
+        }
+    }
+}
+
+// Backtracking moves allowed before Algorithm::Auto gives up on it.
+pub const DEFAULT_FALLBACK_STEPS: usize = 200_000;
+
+// Largest board side solved unless configured otherwise: a 25x25 formula already has 15625 variables.
+pub const DEFAULT_MAX_SIZE: usize = 25;
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Cargo doesn't expose dependency versions: keep this in sync with Cargo.toml.
+pub const SAT_BACKEND: &str = "varisat 0.2.2";
+
+// Which solver actually produced a solution.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SolvedBy {
+    Backtracking,
+    Sat,
+    SatFallback,
+    Dlx,
+}
+
+impl fmt::Display for SolvedBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolvedBy::Backtracking => write!(f, "Backtracking"),
+            SolvedBy::Sat => write!(f, "SAT"),
+            SolvedBy::SatFallback => write!(f, "SAT (fallback)"),
+            SolvedBy::Dlx => write!(f, "DLX"),
+        }
+    }
+}
+
+pub fn solve(matrix: &mut [Vec<i8>], algorithm: Algorithm) -> bool {
+    solve_reporting(matrix, algorithm, &ConstraintSet::default(), DEFAULT_FALLBACK_STEPS, ClueEncoding::default()).is_some()
+}
+
+pub fn solve_time(matrix: &mut [Vec<i8>], algorithm: Algorithm) -> f64 {
+    solve_reporting_time(matrix, algorithm, &ConstraintSet::default(), DEFAULT_FALLBACK_STEPS, ClueEncoding::default()).0
+}
+
+/*
+    Like solve, also telling which solver succeeded. `fallback_steps` only matters for Algorithm::Auto,
+    `clues` only when SAT ends up solving.
+*/
+pub fn solve_reporting(matrix: &mut [Vec<i8>], algorithm: Algorithm, constraints: &ConstraintSet, fallback_steps: usize, clues: ClueEncoding) -> Option<SolvedBy> {
+
+    if let Err(err) = assert_valid_board(matrix) {
+        println!("Cannot solve: {}", err);
+        return None;
+    }
+
+    match algorithm {
+        Algorithm::Backtracking => solve_backtracking_with(matrix, constraints).then_some(SolvedBy::Backtracking),
+        Algorithm::Sat => solve_sat_clues(matrix, constraints, clues).then_some(SolvedBy::Sat),
+        Algorithm::Auto => solve_with_fallback(matrix, constraints, fallback_steps, clues),
+        // DLX only encodes the classic rules: anything more goes to SAT.
+        Algorithm::Dlx if *constraints != ConstraintSet::default() => {
+            println!("DLX doesn't support variants, regions or pencil marks, solving with SAT.");
+            solve_sat_clues(matrix, constraints, clues).then_some(SolvedBy::Sat)
+        }
+        Algorithm::Dlx => solve_dlx(matrix).then_some(SolvedBy::Dlx),
+    }
+}
+
+/*
+    Like solve_reporting, for machines short on memory: boards with a side above `max_size` are refused
+    before any solver state (or SAT formula) is built.
+*/
+pub fn solve_capped(matrix: &mut [Vec<i8>], algorithm: Algorithm, constraints: &ConstraintSet, fallback_steps: usize, clues: ClueEncoding, max_size: usize) -> Result<Option<SolvedBy>, SizeError> {
+    assert_board_within(matrix, max_size)?;
+
+    Ok(solve_reporting(matrix, algorithm, constraints, fallback_steps, clues))
+}
+
+/*
+    Refuses to solve a puzzle whose clues already break a rule, telling which stage stopped:
+    the clue check (with the offending cells) or the solver itself.
+*/
+pub fn validate_and_solve(matrix: &mut [Vec<i8>], algorithm: Algorithm, constraints: &ConstraintSet, fallback_steps: usize, clues: ClueEncoding) -> Result<SolvedBy, SolveStopped> {
+
+    let conflicts = clue_conflicts(matrix, constraints);
+    if !conflicts.is_empty() {
+        return Err(SolveStopped::Conflicts(conflicts));
+    }
+
+    solve_reporting(matrix, algorithm, constraints, fallback_steps, clues).ok_or(SolveStopped::Unsolvable)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SolveStopped {
+    Conflicts(Vec<(usize, usize)>), // Clues breaking a rule, nothing was solved
+    Unsolvable,                     // Clues are consistent but have no solution
+}
+
+impl fmt::Display for SolveStopped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveStopped::Conflicts(cells) => write!(f, "stopped at validation: {} conflicting clue(s)", cells.len()),
+            SolveStopped::Unsolvable => write!(f, "stopped at solving: the puzzle has no solution"),
+        }
+    }
+}
+
+pub fn solve_reporting_time(matrix: &mut [Vec<i8>], algorithm: Algorithm, constraints: &ConstraintSet, fallback_steps: usize, clues: ClueEncoding) -> (f64, Option<SolvedBy>) {
+
+    let start = Instant::now();
+
+    match solve_reporting(matrix, algorithm, constraints, fallback_steps, clues) {
+        Some(solved_by) => (start.elapsed().as_secs_f64(), Some(solved_by)),
+        None => (f64::INFINITY, None),
+    }
+}
+
+// Solve time with a unit fitting its magnitude, e.g. "1.250 s", "3.42 ms", "87.5 µs" (fast solves would read "0.000 s").
+pub fn format_seconds(seconds: f64) -> String {
+    if seconds >= 1. || !seconds.is_finite() {
+        format!("{:.3} s", seconds)
+    } else if seconds >= 1e-3 {
+        format!("{:.2} ms", seconds * 1e3)
+    } else {
+        format!("{:.1} \u{B5}s", seconds * 1e6)
+    }
+}
+
+/*
+    Tries backtracking for at most `max_steps` moves, then hands the original puzzle to SAT.
+    Avoids the pathological backtracking runs that look like infinite loops.
+*/
+pub fn solve_with_fallback(matrix: &mut [Vec<i8>], constraints: &ConstraintSet, max_steps: usize, clues: ClueEncoding) -> Option<SolvedBy> {
+
+    let mut stepper = BacktrackStepper::new(Board::from_matrix(matrix), constraints.clone());
+    stepper.advance(max_steps);
+
+    if !stepper.is_finished() {
+        println!("Backtracking exceeded {} steps, falling back to SAT.", max_steps);
+        return solve_sat_clues(matrix, constraints, clues).then_some(SolvedBy::SatFallback);
+    }
+
+    if stepper.step() == BacktrackStep::Unsolvable {return None}
+
+    stepper.board().write_to(matrix);
+    Some(SolvedBy::Backtracking)
+}
+
+pub fn get_sat_decode(matrix: &[Vec<i8>]) -> String {
+    get_sat_decode_with(matrix, &ConstraintSet::default())
+}
+
+pub fn get_sat_decode_with(matrix: &[Vec<i8>], constraints: &ConstraintSet) -> String {
 
     let mut buf: Vec<u8> = Vec::new();
-    dimacs::write_dimacs(&mut buf, &sudoku_to_sat(matrix)).expect("Write Dimacs err");
+    dimacs::write_dimacs(&mut buf, &sudoku_to_sat_with(matrix, constraints)).expect("Write Dimacs err");
 
     String::from_utf8(buf).expect("String from utf8 err")
 }
 
-// Not using recursion for rust not guaranteeing tail call optimization. Also generally a bad idea.
-pub fn solve_backtracking(matrix: &mut Vec<Vec<i8>>) -> bool {
+/*
+    Like get_sat_decode_with, but past `max_clauses` only the DIMACS header and a short summary are returned.
+    The clause count is computed upfront, so the (possibly huge) formula is never built in that case.
+*/
+pub fn get_sat_decode_bounded(matrix: &[Vec<i8>], constraints: &ConstraintSet, max_clauses: usize) -> String {
+
+    let clauses = sat_clause_count(matrix) + constraints.clause_count(matrix.len());
+
+    if clauses <= max_clauses {
+        return get_sat_decode_with(matrix, constraints);
+    }
+
+    format!(
+        "p cnf {} {}\nc {} clauses exceed the display limit of {}.\nc Export the CNF to a file instead.\n",
+        matrix.len().pow(3), clauses, clauses, max_clauses
+    )
+}
+
+/*
+    Clauses of the formula shown by get_sat_decode_with, grouped by what they encode, in formula order.
+    Readable where the raw CNF isn't: a 16x16 board already has over 120k clauses.
+    Counted on the formula itself, so the categories always add up to what the solver gets.
+*/
+pub fn sat_clause_breakdown(matrix: &[Vec<i8>], constraints: &ConstraintSet) -> Vec<(&'static str, usize)> {
+    sat_clause_groups(matrix, constraints, Encoding::Minimal, ClueEncoding::default())
+        .into_iter()
+        .map(|(category, group)| (category, group.len()))
+        .collect()
+}
+
+// sat_clause_breakdown as text: the DIMACS header, then one line per category.
+pub fn get_sat_summary(matrix: &[Vec<i8>], constraints: &ConstraintSet) -> String {
+    let breakdown = sat_clause_breakdown(matrix, constraints);
+    let total: usize = breakdown.iter().map(|&(_, clauses)| clauses).sum();
+
+    let mut summary = format!("p cnf {} {}\n", matrix.len().pow(3), total);
+
+    for (category, clauses) in breakdown.into_iter().filter(|&(_, clauses)| clauses > 0) {
+        summary.push_str(&format!("{} {} clauses\n", clauses, category));
+    }
+
+    summary
+}
 
+// Number of clauses sudoku_to_sat produces, without building the formula (checked against it in the tests).
+pub fn sat_clause_count(matrix: &[Vec<i8>]) -> usize {
     let size = matrix.len();
+    let pairs = size * size.saturating_sub(1) / 2;
 
-    type Cell: = (usize, usize);
-    let mut positions: Vec<Cell> = Vec::new();
+    size * size + 3 * size * size * pairs + clue_count(matrix)
+}
 
+// Streams the DIMACS CNF straight to a file, without materializing it as a String.
+pub fn export_dimacs(matrix: &[Vec<i8>], constraints: &ConstraintSet, path: &Path) -> io::Result<()> {
+    let mut file = io::BufWriter::new(File::create(path)?);
+    dimacs::write_dimacs(&mut file, &sudoku_to_sat_with(matrix, constraints))?;
+    file.flush()
+}
 
-    for row in 0..size {
-        for col in 0..size {
-            if matrix[row][col] == 0 {
-                positions.push((row, col));
-            }
-        }
+/*
+    Whether a DIMACS CNF is the encoding of `matrix` (as sudoku_to_sat builds it),
+    comparing clauses regardless of their order and of the literal order inside them.
+    Unparsable input simply doesn't match.
+*/
+pub fn dimacs_matches_puzzle(dimacs: &str, matrix: &[Vec<i8>]) -> bool {
+    match dimacs::DimacsParser::parse(dimacs.as_bytes()) {
+        Ok(formula) => normalized_clauses(&formula) == normalized_clauses(&sudoku_to_sat(matrix)),
+        Err(_) => false,
     }
-    
-    let mut i = 0;
-    while i < positions.len() {
-        let pos = positions[i];
-        let mut do_backtrack = true;
+}
 
-        for new_val in matrix[pos.0][pos.1]+1..=size as i8 {
+fn normalized_clauses(formula: &CnfFormula) -> Vec<Vec<Lit>> {
+    let mut clauses: Vec<Vec<Lit>> = formula.iter()
+        .map(|clause| {
+            let mut clause = clause.to_vec();
+            clause.sort();
+            clause
+        })
+        .collect();
 
-            //println!("checking validity of {} for {}, {} (curr value {})", new_val, pos.0, pos.1, matrix[pos.0][pos.1]);
+    clauses.sort();
+    clauses
+}
 
-            if is_value_valid(matrix, new_val, pos){
-                matrix[pos.0][pos.1] = new_val;
-                i += 1;
-                do_backtrack = false;
-                break;
-            }
+// Not using recursion for rust not guaranteeing tail call optimization. Also generally a bad idea.
+pub fn solve_backtracking(matrix: &mut [Vec<i8>]) -> bool {
+    solve_backtracking_with(matrix, &ConstraintSet::default())
+}
 
-        }
+pub fn solve_backtracking_with(matrix: &mut [Vec<i8>], constraints: &ConstraintSet) -> bool {
+    solve_backtracking_logged(matrix, constraints, &mut SolverLog::from_env())
+}
+
+pub fn solve_backtracking_logged(matrix: &mut [Vec<i8>], constraints: &ConstraintSet, log: &mut SolverLog) -> bool {
+
+    if let Err(err) = assert_valid_board(matrix) {
+        println!("Cannot solve: {}", err);
+        return false;
+    }
+
+    if !clue_conflicts(matrix, constraints).is_empty() {
+        println!("Cannot solve: some clues break a rule.");
+        return false;
+    }
+
+    let mut board = Board::from_matrix(matrix);
+    let solved = backtrack_board(&mut board, constraints, log);
+    board.write_to(matrix);
 
-        if do_backtrack {
-            matrix[pos.0][pos.1] = 0;
-            if i == 0 {
+    if solved {debug_assert_solution(matrix, constraints)}
+
+    solved
+}
+
+fn backtrack_board(board: &mut Board, constraints: &ConstraintSet, log: &mut SolverLog) -> bool {
+
+    let mut stepper = BacktrackStepper::new(board.clone(), constraints.clone());
+
+    let solved = loop {
+        match stepper.step() {
+            BacktrackStep::Placed(pos, value) => log.log(format_args!("assign {} {} {}", pos.0, pos.1, value)),
+            BacktrackStep::Backtracked(pos) => log.log(format_args!("backtrack {} {}", pos.0, pos.1)),
+            BacktrackStep::Solved => break true,
+            BacktrackStep::Unsolvable => {
                 /*
                     This is not avoidable with a simple per-cell validity check,
                     as some puzzles can implicitly have some constraints that have no solution(s) even with valid cells.
@@ -77,217 +362,2588 @@ pub fn solve_backtracking(matrix: &mut Vec<Vec<i8>>) -> bool {
                     Note: getting to this point can take A LOT of time and make it look like the function is looping infinitely.
                  */
                 println!("No solution found.");
-                return false;
+                break false;
             }
-            i -= 1;
         }
-    }
+    };
 
-    true
+    log.log(format_args!("result {}", if solved {"solved"} else {"unsolvable"}));
 
+    *board = stepper.board;
+    solved
 }
 
 /*
-    Varisat Documentation: 
-    https://jix.github.io/varisat/manual/0.2.1/lib/basic.html
+    Up to `limit` solutions found by the backtracking engine, in the order it meets them.
+    Independent from the SAT-based collect_solutions, so the two can cross-check each other.
 */
-pub fn solve_sat(matrix: &mut Vec<Vec<i8>>) -> bool {
-    let size = matrix.len();
-    let formula = sudoku_to_sat(matrix);
+pub fn backtracking_all_solutions(matrix: &[Vec<i8>], limit: usize) -> Vec<Vec<Vec<i8>>> {
 
-    let mut solver = Solver::new();
-    solver.add_formula(&formula);
+    if let Err(err) = assert_valid_board(matrix) {
+        println!("Cannot solve: {}", err);
+        return Vec::new();
+    }
 
-    // Check the satisfiability of the current formula.
-    if !solver.solve().unwrap() {
-        return false;
+    let mut stepper = BacktrackStepper::new(Board::from_matrix(matrix), ConstraintSet::default());
+    let mut solutions: Vec<Vec<Vec<i8>>> = Vec::new();
+
+    while solutions.len() < limit {
+        match stepper.step() {
+            BacktrackStep::Solved => {
+                solutions.push(stepper.board().to_matrix());
+                if !stepper.resume() {break}
+            }
+            BacktrackStep::Unsolvable => break,
+            _ => {}
+        }
     }
 
-    let model = solver.model().unwrap();
+    solutions
+}
 
-    // Fill the grid: pick the first true n for each (r, c)
-    for r in 0..size {
-        for c in 0..size {
-            let mut picked: i8 = 0;
-            for n in 0..size {
-                let lit = lit_from_indx(r, c, n, size); // 0-based var index
-                if model.contains(&lit) {
-                    picked = (n as i8) + 1;               // Sudoku digits are 1..=size
-                    break;
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BacktrackStep {
+    Placed((usize, usize), i8),
+    Backtracked((usize, usize)), // Cell cleared, going back to the previous one
+    Solved,
+    Unsolvable,
+}
+
+/*
+    The backtracking solver, one move at a time, so it can be animated or interrupted.
+    Once finished, `step` keeps returning the final state.
+*/
+#[derive(Clone, Debug)]
+pub struct BacktrackStepper {
+    board: Board,
+    constraints: ConstraintSet,
+    positions: Vec<(usize, usize)>, // Empty cells at start, in visiting order
+    i: usize,
+    finished: Option<BacktrackStep>,
+}
+
+impl BacktrackStepper {
+    pub fn new(board: Board, constraints: ConstraintSet) -> Self {
+
+        let size = board.size();
+        let mut positions: Vec<(usize, usize)> = Vec::new();
+
+        for row in 0..size {
+            for col in 0..size {
+                if board.get(row, col) == 0 {
+                    positions.push((row, col));
                 }
             }
-            matrix[r][c] = picked; // stays 0 if none found (Should never happen since satisfiability was previously checked)
         }
+
+        // The search only checks the cells it fills, so clashing givens would otherwise come out as solved.
+        let finished = (!clue_conflicts(&board.to_matrix(), &constraints).is_empty()).then_some(BacktrackStep::Unsolvable);
+
+        Self {board, constraints, positions, i: 0, finished}
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
     }
-    true
-}
 
+    pub fn is_finished(&self) -> bool {
+        self.finished.is_some()
+    }
 
-pub fn is_value_valid(matrix: &Vec<Vec<i8>>, value: i8, pos: (usize, usize)) -> bool {
+    pub fn step(&mut self) -> BacktrackStep {
 
-    if value == 0 {return false;}
+        if let Some(state) = self.finished {return state}
 
-    let size = matrix.len();
-    let sub_size = size.isqrt();
+        if self.i == self.positions.len() {
+            self.finished = Some(BacktrackStep::Solved);
+            return BacktrackStep::Solved;
+        }
+
+        let pos = self.positions[self.i];
+        let size = self.board.size();
+
+        for new_val in self.board.get(pos.0, pos.1)+1..=size as i8 {
+
+            //println!("checking validity of {} for {}, {} (curr value {})", new_val, pos.0, pos.1, self.board.get(pos.0, pos.1));
 
-    for i in 0..size { // Need to jump current pos for iterations before backtrack
-        if (matrix[pos.0][i] == value && i != pos.1) || (matrix[i][pos.1] == value && i != pos.0) {return false};
+            if self.constraints.permits(&self.board, new_val, pos) {
+                self.board.set(pos.0, pos.1, new_val);
+                self.i += 1;
+                return BacktrackStep::Placed(pos, new_val);
+            }
+        }
+
+        self.board.set(pos.0, pos.1, 0);
+
+        if self.i == 0 {
+            self.finished = Some(BacktrackStep::Unsolvable);
+            return BacktrackStep::Unsolvable;
+        }
+
+        self.i -= 1;
+        BacktrackStep::Backtracked(pos)
     }
 
-    let row_sub = pos.0 - (pos.0 % sub_size);
-    let col_sub = pos.1 - (pos.1 % sub_size);
+    /*
+        After a solution, carries on searching for the next one (the last cell moves on to its next value).
+        False if there is nothing to resume: not solved yet, or the puzzle had no blank cell.
+    */
+    pub fn resume(&mut self) -> bool {
+        if self.finished != Some(BacktrackStep::Solved) || self.positions.is_empty() {return false}
+
+        self.finished = None;
+        self.i = self.positions.len() - 1;
+        true
+    }
 
-    for row in 0..sub_size{
-        for col in 0..sub_size {
-            if row + row_sub == pos.0 && col + col_sub == pos.1 {continue}
+    // Runs up to `steps` moves (stopping early once finished), returning how many were made.
+    pub fn advance(&mut self, steps: usize) -> usize {
+        let mut made = 0;
 
-            if matrix[row + row_sub][col + col_sub] == value {return false}
+        while made < steps && !self.is_finished() {
+            self.step();
+            made += 1;
         }
+
+        made
     }
-    true
 }
 
-pub fn is_matrix_valid(matrix: &Vec<Vec<i8>>) -> Vec<(usize, usize)> {
-    
+/*
+    Varisat Documentation: 
+    https://jix.github.io/varisat/manual/0.2.1/lib/basic.html
+*/
+pub fn solve_sat(matrix: &mut [Vec<i8>]) -> bool {
+    solve_sat_with(matrix, &ConstraintSet::default())
+}
+
+pub fn solve_sat_with(matrix: &mut [Vec<i8>], constraints: &ConstraintSet) -> bool {
+    solve_sat_encoded(matrix, constraints, Encoding::Minimal)
+}
+
+pub fn solve_sat_encoded(matrix: &mut [Vec<i8>], constraints: &ConstraintSet, encoding: Encoding) -> bool {
+    solve_sat_report(matrix, constraints, encoding, ClueEncoding::default()).solved
+}
+
+pub fn solve_sat_clues(matrix: &mut [Vec<i8>], constraints: &ConstraintSet, clues: ClueEncoding) -> bool {
+    solve_sat_report(matrix, constraints, Encoding::Minimal, clues).solved
+}
+
+// Same as solve_sat_encoded, also telling how big the formula handed to varisat was.
+pub fn solve_sat_report(matrix: &mut [Vec<i8>], constraints: &ConstraintSet, encoding: Encoding, clues: ClueEncoding) -> SatSolveReport {
     let size = matrix.len();
 
-    type Cell: = (usize, usize);
-    let mut inv_pos: Vec<Cell> = Vec::new();
+    if let Err(err) = assert_valid_board(matrix) {
+        println!("Cannot solve: {}", err);
+        return SatSolveReport {solved: false, variables: 0, clauses: 0, literals: 0, estimated_bytes: 0};
+    }
+    let formula = sudoku_to_sat_clues(matrix, constraints, encoding, clues);
 
-    for row in 0..size {
-        for col in 0..size {
-            if !(is_value_valid(matrix, matrix[row][col], (row, col))) {
-                inv_pos.push((row, col));
-            }
+    let mut report = SatSolveReport::for_formula(&formula);
+
+    let mut log = SolverLog::from_env();
+    log.log(format_args!("sat {} variables {} clauses", report.variables, report.clauses));
+
+    let mut solver = Solver::new();
+    solver.add_formula(&formula);
+
+    // Check the satisfiability of the current formula.
+    if !solver.solve().unwrap() {
+        log.log(format_args!("result unsatisfiable"));
+        return report;
+    }
+
+    log.log(format_args!("result satisfiable"));
+
+    board_from_model(&solver.model().unwrap(), size).write_to(matrix);
+    debug_assert_solution(matrix, constraints);
+    report.solved = true;
+    report
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SatSolveReport {
+    pub solved: bool,
+    pub variables: usize,
+    pub clauses: usize,
+    pub literals: usize,
+    pub estimated_bytes: usize, // Rough peak memory of the formula once loaded in the solver
+}
+
+impl SatSolveReport {
+    /*
+        The estimate only covers the input: clause literals and headers plus per-variable solver state.
+        Learnt clauses come on top of it, so treat it as an order of magnitude.
+    */
+    fn for_formula(formula: &CnfFormula) -> Self {
+        const CLAUSE_HEADER_BYTES: usize = 16;
+        const VARIABLE_BYTES: usize = 64; // Assignment, trail, watch lists, activity...
+
+        let literals: usize = formula.iter().map(|clause| clause.len()).sum();
+
+        SatSolveReport {
+            solved: false,
+            variables: formula.var_count(),
+            clauses: formula.len(),
+            literals,
+            estimated_bytes: literals * std::mem::size_of::<Lit>() + formula.len() * CLAUSE_HEADER_BYTES + formula.var_count() * VARIABLE_BYTES,
         }
     }
+}
 
-    inv_pos
+pub fn find_unsatisfiable_core(matrix: &[Vec<i8>]) -> Vec<(usize, usize)> {
+    find_unsatisfiable_core_with(matrix, &ConstraintSet::default())
 }
 
 /*
-    Note: This algorithm does not always generate actual solvable puzzles.
-    It only checks essential constraints but this is not enough to guarantee it.
+    Filled cells that together make the puzzle unsolvable, empty if it has a solution
+    (or if the rules and constraints can't be satisfied even on an empty grid).
+    The filled cells are passed to varisat as assumptions instead of unit clauses, so an UNSAT answer comes with
+    the subset of them it relied on. That core is then shrunk one cell at a time until removing any cell
+    makes the rest solvable, i.e. it is minimal (not necessarily the smallest one).
 */
-pub fn generate_random_matrix(matrix: &mut Vec<Vec<i8>>, rnd_size: usize) {
-    let size = matrix.len();
+pub fn find_unsatisfiable_core_with(matrix: &[Vec<i8>], constraints: &ConstraintSet) -> Vec<(usize, usize)> {
 
-    for _ in 0..rnd_size {
-        let row = rng().random_range(0..size);
-        let col = rng().random_range(0..size);
+    if assert_valid_board(matrix).is_err() {return Vec::new()}
 
-        while matrix[row][col] == 0 {
-            let new_value = rng().random_range(1..=size) as i8;
+    let size = matrix.len();
 
-            if is_value_valid(matrix, new_value, (row, col)) {
-                matrix[row][col] = new_value;
-            }
+    let mut solver = Solver::new();
+    solver.add_formula(&sudoku_to_sat_with(&vec![vec![0; size]; size], constraints));
 
-            
+    let mut cells: Vec<(Lit, (usize, usize))> = Vec::new();
+    for (row, values) in matrix.iter().enumerate() {
+        for (col, &value) in values.iter().enumerate() {
+            if value < 1 || value as usize > size {continue}
+            cells.push((lit_from_indx(row, col, value_to_index(value), size), (row, col)));
         }
     }
 
-    println!("Completed random seed.");
+    let mut failed_core = |assumptions: &[Lit]| -> Option<Vec<Lit>> {
+        solver.assume(assumptions);
+        if solver.solve().unwrap() {None} else {solver.failed_core().map(<[Lit]>::to_vec)}
+    };
+
+    let all: Vec<Lit> = cells.iter().map(|&(lit, _)| lit).collect();
+    let Some(mut core) = failed_core(&all) else {return Vec::new()};
+
+    // Deletion-based minimization: drop each cell whose absence keeps the rest UNSAT.
+    let mut i = 0;
+    while i < core.len() {
+        let mut without = core.clone();
+        without.remove(i);
+
+        match failed_core(&without) {
+            Some(smaller) => core.retain(|lit| smaller.contains(lit)), // May also drop later cells
+            None => i += 1,
+        }
+    }
 
+    cells.into_iter()
+        .filter(|(lit, _)| core.contains(lit))
+        .map(|(_, pos)| pos)
+        .collect()
 }
 
 /*
-    SOURCE: https://sat.inesc-id.pt/~ines/publications/aimath06.pdf
-    Generates 3(n^2)
-    Uses DIMACS CNF representation https://people.sc.fsu.edu/~jburkardt/data/cnf/cnf.html
+    Fills only block `block` of `constraints.blocks` (0-based: boxes in reading order, or the jigsaw regions),
+    for showing how one box gets completed.
+    Like find_unsatisfiable_core_with, the filled cells are assumptions on the empty-grid formula, so the block
+    ends up consistent with a completion of the whole board. None if there is no such completion (or no such block).
 */
+pub fn solve_region(matrix: &[Vec<i8>], constraints: &ConstraintSet, block: usize) -> Option<Vec<Vec<i8>>> {
 
-fn lit_from_indx(row: usize, col: usize, n: usize, size: usize) -> Lit {
-    // Varisat uses 0-based var indices; `true` means positive literal.
-    /*
-        We need to create an index that is unique, dense and calculated in O(1) for each matrix cell regardless of its value.
+    assert_valid_board(matrix).ok()?;
 
-        Since n has the same range of values of row and col, I decided to treat the matrix as a 3d-array (cube) with N1=N2=N3= size.
+    let size = matrix.len();
+    let cells = constraints.blocks(size).into_iter().nth(block)?;
 
-        This allows to use general array address calculation https://en.wikipedia.org/wiki/Row-_and_column-major_order
-     */
+    let mut solver = Solver::new();
+    solver.add_formula(&sudoku_to_sat_with(&vec![vec![0; size]; size], constraints));
 
-    Lit::from_index(n + size * (col + size * row), true)
+    let clues: Vec<Lit> = matrix.iter().enumerate()
+        .flat_map(|(row, values)| values.iter().enumerate().map(move |(col, &value)| (row, col, value)))
+        .filter(|&(_, _, value)| value >= 1 && value as usize <= size)
+        .map(|(row, col, value)| lit_from_indx(row, col, value_to_index(value), size))
+        .collect();
 
-}
+    solver.assume(&clues);
+    if !solver.solve().unwrap() {return None}
 
-/// Build CNF for Sudoku with:
-///  - ALO per cell
-///  - AMO per row/col/block (for each number)
-pub fn sudoku_to_sat(matrix: &Vec<Vec<i8>>) -> CnfFormula {
+    let solution = board_from_model(&solver.model().unwrap(), size);
+    let mut result = matrix.to_vec();
+    for (row, col) in cells {
+        result[row][col] = solution.get(row, col);
+    }
 
-    let size = matrix.len();
-    let sub_size = size.isqrt(); 
+    Some(result)
+}
 
-    let mut formula = CnfFormula::new();
+fn board_from_model(model: &[Lit], size: usize) -> Board {
+    let mut board = Board::new(size);
 
-    // 1) Each cell has AT LEAST ONE number
+    // Fill the grid: pick the first true n for each (r, c)
     for r in 0..size {
         for c in 0..size {
-            let mut clause: Vec<Lit> = Vec::with_capacity(size);
             for n in 0..size {
-                clause.push(lit_from_indx(r, c, n, size));
+                let lit = lit_from_indx(r, c, n, size); // 0-based var index
+                if model.contains(&lit) {
+                    board.set(r, c, index_to_value(n));
+                    break;
+                }
             }
-            formula.add_clause(&clause);
+            // Cell stays 0 if none found (Should never happen since satisfiability was previously checked)
         }
     }
 
-    // 2) Each number appears at most once in each row
-    for r in 0..size {
-        for n in 0..size {
-            for c1 in 0..size {
-                for c2 in (c1 + 1)..size {
-                    let a = lit_from_indx(r, c1, n, size);
-                    let b = lit_from_indx(r, c2, n, size);
-                    formula.add_clause(&[!a, !b]);
+    board
+}
+
+
+pub fn is_completable(matrix: &[Vec<i8>]) -> bool {
+    is_completable_with(matrix, &ConstraintSet::default())
+}
+
+// Whether the filled cells, taken as clues, still lead to at least one solution. The board is left untouched.
+pub fn is_completable_with(matrix: &[Vec<i8>], constraints: &ConstraintSet) -> bool {
+
+    if assert_valid_board(matrix).is_err() || !clue_conflicts(matrix, constraints).is_empty() {return false}
+
+    let mut solver = Solver::new();
+    solver.add_formula(&sudoku_to_sat_with(matrix, constraints));
+
+    solver.solve().unwrap()
+}
+
+// Counts the solutions of `matrix`, stopping at `limit` (use 2 to check uniqueness).
+pub fn count_solutions(matrix: &[Vec<i8>], limit: usize) -> usize {
+    count_solutions_with(matrix, &ConstraintSet::default(), limit)
+}
+
+pub fn count_solutions_with(matrix: &[Vec<i8>], constraints: &ConstraintSet, limit: usize) -> usize {
+    for_each_solution(matrix, constraints, limit, |_| {})
+}
+
+// Up to `limit` solutions of `matrix`, e.g. to show why an ambiguous puzzle is improper.
+pub fn collect_solutions(matrix: &[Vec<i8>], limit: usize) -> Vec<Vec<Vec<i8>>> {
+    collect_solutions_with(matrix, &ConstraintSet::default(), limit)
+}
+
+pub fn collect_solutions_with(matrix: &[Vec<i8>], constraints: &ConstraintSet, limit: usize) -> Vec<Vec<Vec<i8>>> {
+    let mut solutions = Vec::new();
+    for_each_solution(matrix, constraints, limit, |solution| solutions.push(solution.to_vec()));
+    solutions
+}
+
+/*
+    Calls `on_solution` with each solution of `matrix` under `constraints`, up to `limit` of them, and returns how many were found.
+    After each model, a clause forbidding that exact assignment is added and the solver runs again,
+    so solutions are streamed instead of collected. A malformed board has none.
+*/
+pub fn for_each_solution(matrix: &[Vec<i8>], constraints: &ConstraintSet, limit: usize, mut on_solution: impl FnMut(&[Vec<i8>])) -> usize {
+
+    if let Err(err) = assert_valid_board(matrix) {
+        println!("Cannot count solutions: {}", err);
+        return 0;
+    }
+
+    let size = matrix.len();
+
+    let mut solver = Solver::new();
+    solver.add_formula(&sudoku_to_sat_with(matrix, constraints));
+
+    let mut count = 0;
+
+    while count < limit && solver.solve().unwrap() {
+        count += 1;
+
+        let model = solver.model().unwrap();
+        on_solution(&board_from_model(&model, size).to_matrix());
+
+        // Only cell/value variables matter: any auxiliary ones would make equal grids look different.
+        let blocking: Vec<Lit> = model.iter()
+            .filter(|lit| lit.is_positive() && lit.index() < size.pow(3))
+            .map(|&lit| !lit)
+            .collect();
+
+        solver.add_clause(&blocking);
+    }
+
+    count
+}
+
+// Classic rules only, read straight from the nested rows (no Board copy): for one-off checks on GUI matrices.
+pub fn is_value_valid(matrix: &[Vec<i8>], value: i8, pos: (usize, usize)) -> bool {
+
+    let size = matrix.len();
+    if value < 1 || value as usize > size {return false}
+
+    // Other cells only: the current position may already hold `value`.
+    let in_row = matrix[pos.0].iter().enumerate().any(|(col, &v)| v == value && col != pos.1);
+    let in_col = matrix.iter().enumerate().any(|(row, values)| values[pos.1] == value && row != pos.0);
+    if in_row || in_col {return false}
+
+    let sub_size = size.isqrt();
+    let row_sub = pos.0 - (pos.0 % sub_size);
+    let col_sub = pos.1 - (pos.1 % sub_size);
+
+    !matrix.iter().enumerate().skip(row_sub).take(sub_size)
+        .any(|(row, values)| values.iter().enumerate().skip(col_sub).take(sub_size).any(|(col, &v)| v == value && (row, col) != pos))
+}
+
+// Values that would break a rule at `pos` given the other cells (the cell's own value is ignored).
+pub fn conflicting_values(matrix: &[Vec<i8>], pos: (usize, usize)) -> Vec<i8> {
+    conflicting_values_with(matrix, pos, &ConstraintSet::default())
+}
+
+pub fn conflicting_values_with(matrix: &[Vec<i8>], pos: (usize, usize), constraints: &ConstraintSet) -> Vec<i8> {
+    let board = Board::from_matrix(matrix);
+
+    (1..=board.size() as i8)
+        .filter(|&value| !constraints.permits(&board, value, pos))
+        .collect()
+}
+
+/*
+    Legal values of every cell as a bitmask, bit k set when value k + 1 is allowed (classic rules).
+    Filled cells get 0. Meant for fast solvers working on bits instead of Vec<i8>; boards up to 25x25 fit in a u32.
+*/
+pub fn candidate_bitmasks(matrix: &[Vec<i8>]) -> Vec<Vec<u32>> {
+    let board = Board::from_matrix(matrix);
+    let size = board.size();
+    assert!(size <= u32::BITS as usize, "Bitmasks support sizes up to {}, got {}", u32::BITS, size);
+
+    (0..size)
+        .map(|row| (0..size)
+            .map(|col| board.candidates((row, col))
+                .into_iter()
+                .fold(0, |mask, value| mask | 1 << value_to_index(value)))
+            .collect())
+        .collect()
+}
+
+// Filled cells that break a rule; unlike is_matrix_valid_with, blanks are not reported.
+pub fn clue_conflicts(matrix: &[Vec<i8>], constraints: &ConstraintSet) -> Vec<(usize, usize)> {
+    is_matrix_valid_with(matrix, constraints)
+        .into_iter()
+        .filter(|&(row, col)| matrix[row][col] != 0)
+        .collect()
+}
+
+pub fn is_matrix_valid(matrix: &[Vec<i8>]) -> Vec<(usize, usize)> {
+    is_matrix_valid_with(matrix, &ConstraintSet::default())
+}
+
+pub fn is_matrix_valid_with(matrix: &[Vec<i8>], constraints: &ConstraintSet) -> Vec<(usize, usize)> {
+    
+    let board = Board::from_matrix(matrix);
+    let size = board.size();
+
+    type Cell = (usize, usize);
+    let mut inv_pos: Vec<Cell> = Vec::new();
+
+    for row in 0..size {
+        for col in 0..size {
+            let value = board.get(row, col);
+            if !constraints.permits(&board, value, (row, col)) {
+                inv_pos.push((row, col));
+            }
+        }
+    }
+
+    inv_pos
+}
+
+// Every cell filled and no rule broken: what a solver must hand back when it reports success.
+pub fn is_complete_solution(matrix: &[Vec<i8>]) -> bool {
+    is_complete_solution_with(matrix, &ConstraintSet::default())
+}
+
+pub fn is_complete_solution_with(matrix: &[Vec<i8>], constraints: &ConstraintSet) -> bool {
+    empty_count(matrix) == 0 && is_matrix_valid_with(matrix, constraints).is_empty()
+}
+
+// Debug builds check every reported solution, so an encoding or decoding bug fails loudly instead of showing a wrong grid.
+fn debug_assert_solution(matrix: &[Vec<i8>], constraints: &ConstraintSet) {
+    debug_assert!(is_complete_solution_with(matrix, constraints), "Solver returned an invalid solution:\n{}", pretty_print(matrix));
+}
+
+/*
+    The cell after `pos` in reading order (left to right, then the next row).
+    At the last cell: the first one if `wrap`, otherwise None.
+*/
+pub fn next_cell(size: usize, pos: (usize, usize), wrap: bool) -> Option<(usize, usize)> {
+    let index = pos.0 * size + pos.1 + 1;
+
+    if index < size * size {
+        Some((index / size, index % size))
+    } else {
+        wrap.then_some((0, 0))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+// The neighbour of `pos` in `direction`, staying on `pos` at the edge of the board.
+pub fn move_cell(size: usize, pos: (usize, usize), direction: Direction) -> (usize, usize) {
+    let (row, col) = pos;
+
+    match direction {
+        Direction::Up => (row.saturating_sub(1), col),
+        Direction::Down => ((row + 1).min(size - 1), col),
+        Direction::Left => (row, col.saturating_sub(1)),
+        Direction::Right => (row, (col + 1).min(size - 1)),
+    }
+}
+
+// Cells sharing a row, column or block (the jigsaw region, if any) with `pos`, excluding `pos` itself, on a size x size board.
+pub fn affected_cells(size: usize, pos: (usize, usize), constraints: &ConstraintSet) -> Vec<(usize, usize)> {
+
+    let mut peers: Vec<(usize, usize)> = Vec::with_capacity(3 * size);
+
+    for i in 0..size {
+        if i != pos.1 {peers.push((pos.0, i))}
+        if i != pos.0 {peers.push((i, pos.1))}
+    }
+
+    // Block cells outside the current row and column (those were already added)
+    let block = constraints.block_of(size, pos);
+    if let Some(cells) = constraints.blocks(size).get(block) {
+        peers.extend(cells.iter().filter(|&&(row, col)| row != pos.0 && col != pos.1));
+    }
+
+    peers
+}
+
+// Filled cells of the board (clues, on a fresh puzzle).
+pub fn clue_count(matrix: &[Vec<i8>]) -> usize {
+    matrix.iter().flatten().filter(|&&v| v != 0).count()
+}
+
+pub fn empty_count(matrix: &[Vec<i8>]) -> usize {
+    matrix.iter().flatten().filter(|&&v| v == 0).count()
+}
+
+// How many times each digit is placed: index k counts value k + 1, `size` meaning the digit is complete.
+pub fn digit_counts(matrix: &[Vec<i8>]) -> Vec<usize> {
+    let mut counts = vec![0; matrix.len()];
+
+    for &value in matrix.iter().flatten().filter(|&&v| v > 0 && v as usize <= matrix.len()) {
+        counts[value_to_index(value)] += 1;
+    }
+
+    counts
+}
+
+// Cells whose values differ between two boards of the same size.
+pub fn diff_cells(a: &[Vec<i8>], b: &[Vec<i8>]) -> Vec<(usize, usize)> {
+    let mut diff: Vec<(usize, usize)> = Vec::new();
+
+    for (row, (row_a, row_b)) in a.iter().zip(b).enumerate() {
+        for (col, (value_a, value_b)) in row_a.iter().zip(row_b).enumerate() {
+            if value_a != value_b {diff.push((row, col))}
+        }
+    }
+
+    diff
+}
+
+// Where the value of a cell came from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CellOrigin {
+    #[default]
+    Empty,
+    Given,  // Part of the puzzle as generated or loaded
+    Player, // Entered by hand
+    Solver, // Filled by a solver
+}
+
+impl CellOrigin {
+    // `origin` for a filled cell; a cleared cell has no origin left.
+    pub fn for_value(value: i8, origin: CellOrigin) -> CellOrigin {
+        if value == 0 {CellOrigin::Empty} else {origin}
+    }
+}
+
+// Provenance of a freshly loaded puzzle: every filled cell is a clue.
+pub fn given_origins(matrix: &[Vec<i8>]) -> Vec<Vec<CellOrigin>> {
+    matrix.iter()
+        .map(|row| row.iter().map(|&value| CellOrigin::for_value(value, CellOrigin::Given)).collect())
+        .collect()
+}
+
+// Cells that differ between `before` and `after` now come from `origin` (or are Empty); the others keep theirs.
+pub fn update_origins(origins: &mut [Vec<CellOrigin>], before: &[Vec<i8>], after: &[Vec<i8>], origin: CellOrigin) {
+    for (row, col) in diff_cells(before, after) {
+        origins[row][col] = CellOrigin::for_value(after[row][col], origin);
+    }
+}
+
+// Filled cells of `user` that disagree with the known solution. Blanks are not mistakes, just unfinished.
+pub fn check_against_solution(user: &[Vec<i8>], solution: &[Vec<i8>]) -> Vec<(usize, usize)> {
+    diff_cells(user, solution)
+        .into_iter()
+        .filter(|&(row, col)| user[row][col] != 0)
+        .collect()
+}
+
+// Relabels every `a` as `b` and vice versa, giving an equivalent puzzle. Returns false for out of range digits.
+pub fn swap_digits(matrix: &mut [Vec<i8>], a: i8, b: i8) -> bool {
+    let size = matrix.len() as i8;
+
+    if !(1..=size).contains(&a) || !(1..=size).contains(&b) {return false}
+
+    for value in matrix.iter_mut().flatten() {
+        if *value == a {*value = b} else if *value == b {*value = a}
+    }
+
+    true
+}
+
+/*
+    Single-line puzzle format: one character per cell, row by row.
+    Digits use base 36 ('1'..'9' then 'A'..'Z') so every size up to 25x25 keeps one char per cell.
+    Blanks are written as '0'; '.' and '_' are also accepted when parsing. Whitespace is ignored.
+*/
+pub fn export_puzzle_to_string(matrix: &[Vec<i8>]) -> String {
+    export_puzzle_to_string_with(matrix, BlankStyle::Zero)
+}
+
+pub fn export_puzzle_to_string_with(matrix: &[Vec<i8>], blank: BlankStyle) -> String {
+    matrix.iter()
+        .flatten()
+        .map(|&v| cell_char(v, blank))
+        .collect()
+}
+
+fn cell_char(value: i8, blank: BlankStyle) -> char {
+    if value == 0 {blank.export_char()} else {char::from_digit(value as u32, 36).map_or('0', |c| c.to_ascii_uppercase())}
+}
+
+// Value of a typed character on a board of side `size`, read like the single-line format ('A' or 'a' is 10). None past the board's values.
+pub fn value_from_char(ch: char, size: usize) -> Option<i8> {
+    ch.to_digit(36).filter(|&value| value >= 1 && value as usize <= size).map(|value| value as i8)
+}
+
+/*
+    Human-readable grid for terminals and bug reports, with the same characters as the single-line format
+    and '.' for blanks. Blocks are framed, e.g. for 4x4:
+        +-----+-----+
+        | 1 . | 3 4 |
+        | 3 4 | . 2 |
+        +-----+-----+
+        | . 1 | 4 3 |
+        | 4 3 | 2 . |
+        +-----+-----+
+*/
+pub fn pretty_print(matrix: &[Vec<i8>]) -> String {
+    let sub_size = matrix.len().isqrt();
+    let separator = format!("+{}\n", format!("{}+", "-".repeat(2 * sub_size + 1)).repeat(sub_size));
+
+    let mut text = String::new();
+
+    for (row, values) in matrix.iter().enumerate() {
+        if row % sub_size == 0 {text.push_str(&separator)}
+
+        for (col, &value) in values.iter().enumerate() {
+            if col % sub_size == 0 {text.push_str("| ")}
+            text.push(cell_char(value, BlankStyle::Dot));
+            text.push(' ');
+        }
+        text.push_str("|\n");
+    }
+
+    text.push_str(&separator);
+    text
+}
+
+/*
+    Constraint graph in Graphviz DOT: one node per cell (labelled with its clue, '.' if blank), one edge per pair
+    of peers, i.e. cells sharing a row, column or block. Solving the puzzle is coloring this graph with `size` colors.
+    Render it with e.g. `neato -Tsvg sudoku.dot`; nodes are pinned to their grid position.
+*/
+pub fn to_dot(matrix: &[Vec<i8>]) -> String {
+    let size = matrix.len();
+
+    // A pair sharing two units (row and block, column and block) is still a single edge.
+    let edges: BTreeSet<((usize, usize), (usize, usize))> = units(size).iter()
+        .flat_map(|unit| unit.iter().flat_map(move |&a| unit.iter().filter(move |&&b| a < b).map(move |&b| (a, b))))
+        .collect();
+
+    let mut dot = String::from("graph sudoku {\n    node [shape=circle];\n");
+
+    for (row, values) in matrix.iter().enumerate() {
+        for (col, &value) in values.iter().enumerate() {
+            dot.push_str(&format!("    r{}c{} [label=\"{}\", pos=\"{},{}!\"];\n", row, col, cell_char(value, BlankStyle::Dot), col, size - 1 - row));
+        }
+    }
+
+    for ((r1, c1), (r2, c2)) in edges {
+        dot.push_str(&format!("    r{}c{} -- r{}c{};\n", r1, c1, r2, c2));
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+// How blank cells are written in exported strings and drawn on the grid.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlankStyle {
+    Empty,
+    Dot,
+    #[default]
+    Zero,
+}
+
+impl BlankStyle {
+    pub const ALL: [BlankStyle; 3] = [BlankStyle::Empty, BlankStyle::Dot, BlankStyle::Zero];
+
+    // What the grid shows in a blank cell.
+    pub fn glyph(self) -> &'static str {
+        match self {
+            BlankStyle::Empty => " ",
+            BlankStyle::Dot => ".",
+            BlankStyle::Zero => "0",
+        }
+    }
+
+    // One char per cell has to stay: "empty" exports as '_', since whitespace is skipped when parsing.
+    pub fn export_char(self) -> char {
+        match self {
+            BlankStyle::Empty => '_',
+            BlankStyle::Dot => '.',
+            BlankStyle::Zero => '0',
+        }
+    }
+}
+
+impl fmt::Display for BlankStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlankStyle::Empty => write!(f, "Empty"),
+            BlankStyle::Dot => write!(f, "Dot (.)"),
+            BlankStyle::Zero => write!(f, "Zero (0)"),
+        }
+    }
+}
+
+/*
+    Block side (the GUI's matrix size) of a board with `cell_count` cells, i.e. the n with n^4 = cell_count:
+    1, 16, 81, 256, 625... give 1 to 5. That power is injective, so a count never matches two sizes;
+    None for 0 and for any count in between.
+*/
+pub fn infer_size(cell_count: usize) -> Option<usize> {
+    let sub_size = cell_count.isqrt().isqrt();
+
+    (cell_count > 0 && sub_size.pow(4) == cell_count).then_some(sub_size)
+}
+
+pub fn parse_puzzle(text: &str) -> Result<Vec<Vec<i8>>, ParseError> {
+
+    let mut cells: Vec<i8> = Vec::new();
+
+    for ch in text.chars().filter(|ch| !ch.is_whitespace()) {
+        match ch {
+            '.' | '_' => cells.push(0),
+            _ => cells.push(ch.to_digit(36).ok_or(ParseError::InvalidChar(ch))? as i8),
+        }
+    }
+
+    let size = infer_size(cells.len()).ok_or(ParseError::InvalidLength(cells.len()))?.pow(2);
+
+    if let Some(&value) = cells.iter().find(|&&v| v as usize > size) {
+        return Err(ParseError::ValueOutOfRange(value));
+    }
+
+    Ok(cells.chunks(size).map(|row| row.to_vec()).collect())
+}
+
+/*
+    Multi-line grid for boards of side `size`: one row per line, cells separated by spaces and/or commas.
+    Values are decimal (so 10..=25 fit on big boards); blanks are 0, '.' or '_'. Empty lines are skipped.
+*/
+pub fn parse_grid_rows(text: &str, size: usize) -> Result<Vec<Vec<i8>>, ParseError> {
+
+    let mut matrix: Vec<Vec<i8>> = Vec::new();
+
+    for line in text.lines().filter(|line| !line.trim().is_empty()) {
+        let row = line.split(|ch: char| ch == ',' || ch.is_whitespace())
+            .filter(|token| !token.is_empty())
+            .map(|token| match token {
+                "." | "_" => Ok(0),
+                _ => match token.parse::<i8>() {
+                    Ok(value) if value < 0 => Err(ParseError::InvalidNumber(token.to_string())),
+                    Ok(value) if value as usize > size => Err(ParseError::ValueOutOfRange(value)),
+                    Ok(value) => Ok(value),
+                    Err(_) => Err(ParseError::InvalidNumber(token.to_string())),
+                },
+            })
+            .collect::<Result<Vec<i8>, ParseError>>()?;
+
+        if row.len() != size {
+            return Err(ParseError::RowLength {row: matrix.len(), len: row.len()});
+        }
+        matrix.push(row);
+    }
+
+    if matrix.len() != size {
+        return Err(ParseError::WrongSize {expected: size, found: matrix.len()});
+    }
+
+    Ok(matrix)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    InvalidChar(char),
+    InvalidLength(usize),
+    ValueOutOfRange(i8),
+    InvalidNumber(String),
+    WrongSize {expected: usize, found: usize}, // Row count of a multi-line grid
+    RowLength {row: usize, len: usize},        // Row (0-based) with a different number of cells
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::InvalidChar(ch) => write!(f, "invalid character '{}'", ch),
+            ParseError::InvalidLength(len) => write!(f, "{} cells do not form a square board", len),
+            ParseError::ValueOutOfRange(value) => write!(f, "value {} is too big for this board", value),
+            ParseError::InvalidNumber(token) => write!(f, "'{}' is not a cell value", token),
+            ParseError::WrongSize {expected, found} => write!(f, "expected {} rows, got {}", expected, found),
+            ParseError::RowLength {row, len} => write!(f, "row {} has {} cells", row + 1, len),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/*
+    Blocks are sub_size x sub_size with sub_size = isqrt(size), which is only right when size is a perfect square.
+    Every solver checks this first instead of silently working on a broken block layout.
+*/
+pub fn assert_valid_size(size: usize) -> Result<(), SizeError> {
+    if size == 0 {return Err(SizeError::Empty)}
+    if size.isqrt().pow(2) != size {return Err(SizeError::NotSquare(size))}
+
+    Ok(())
+}
+
+// assert_valid_size on the board side, plus every row having that many cells.
+pub fn assert_valid_board(matrix: &[Vec<i8>]) -> Result<(), SizeError> {
+    assert_valid_size(matrix.len())?;
+
+    match matrix.iter().position(|row| row.len() != matrix.len()) {
+        Some(row) => Err(SizeError::RowLength {row, len: matrix[row].len()}),
+        None => Ok(()),
+    }
+}
+
+// assert_valid_board, plus a side of at most `max_size`.
+pub fn assert_board_within(matrix: &[Vec<i8>], max_size: usize) -> Result<(), SizeError> {
+    if matrix.len() > max_size {return Err(SizeError::TooLarge {size: matrix.len(), max: max_size})}
+
+    assert_valid_board(matrix)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeError {
+    Empty,
+    NotSquare(usize), // No integer block side for this board side
+    RowLength {row: usize, len: usize},
+    TooLarge {size: usize, max: usize}, // Side above the configured cap
+}
+
+impl fmt::Display for SizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SizeError::Empty => write!(f, "the board is empty"),
+            SizeError::NotSquare(size) => write!(f, "a {}x{} board can't be split into square blocks", size, size),
+            SizeError::RowLength {row, len} => write!(f, "row {} has {} cells, the board isn't square", row, len),
+            SizeError::TooLarge {size, max} => write!(f, "board too large for available memory ({}x{}, the limit is {}x{})", size, size, max, max),
+        }
+    }
+}
+
+impl std::error::Error for SizeError {}
+
+// Fixed-size copy for callers that only deal with classic 9x9 boards.
+pub fn to_array9(matrix: &[Vec<i8>]) -> Result<[[i8; 9]; 9], DimensionError> {
+
+    if let Some(row) = matrix.iter().find(|row| row.len() != 9) {
+        return Err(DimensionError {rows: matrix.len(), cols: row.len()});
+    }
+    if matrix.len() != 9 {
+        return Err(DimensionError {rows: matrix.len(), cols: 9});
+    }
+
+    let mut array = [[0i8; 9]; 9];
+    for (row, values) in array.iter_mut().zip(matrix) {
+        row.copy_from_slice(values);
+    }
+
+    Ok(array)
+}
+
+pub fn from_array9(array: &[[i8; 9]; 9]) -> Vec<Vec<i8>> {
+    array.iter().map(|row| row.to_vec()).collect()
+}
+
+// The board doesn't have the expected shape (cols is the length of the first offending row).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DimensionError {
+    pub rows: usize,
+    pub cols: usize,
+}
+
+impl fmt::Display for DimensionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected a 9x9 board, got {}x{}", self.rows, self.cols)
+    }
+}
+
+impl std::error::Error for DimensionError {}
+
+/*
+    Note: This algorithm does not always generate actual solvable puzzles.
+    It only checks essential constraints but this is not enough to guarantee it.
+*/
+pub fn generate_random_matrix(matrix: &mut [Vec<i8>], rnd_size: usize) {
+    generate_random_matrix_with(matrix, rnd_size, &mut rng());
+}
+
+// Same as generate_random_matrix, drawing from `random` (e.g. a seeded StdRng for reproducible boards).
+pub fn generate_random_matrix_with(matrix: &mut [Vec<i8>], rnd_size: usize, random: &mut impl Rng) {
+    let mut board = Board::from_matrix(matrix);
+    let size = board.size();
+
+    for _ in 0..rnd_size {
+        let row = random.random_range(0..size);
+        let col = random.random_range(0..size);
+
+        if board.get(row, col) != 0 {continue}
+
+        // Picking among the legal values only: a blind retry loop would spin forever on a cell with none left.
+        let candidates: Vec<i8> = (1..=size as i8).filter(|&v| board.is_value_valid(v, (row, col))).collect();
+
+        if !candidates.is_empty() {
+            board.set(row, col, candidates[random.random_range(0..candidates.len())]);
+        }
+    }
+
+    board.write_to(matrix);
+    println!("Completed random seed.");
+
+}
+
+/*
+    A random empty cell and one of its currently legal values, for guessing practice.
+    The value only respects the rules so far: it isn't necessarily the one of the solution.
+*/
+pub fn suggest_move(matrix: &[Vec<i8>]) -> Option<((usize, usize), i8)> {
+    suggest_move_with(matrix, &ConstraintSet::default())
+}
+
+pub fn suggest_move_with(matrix: &[Vec<i8>], constraints: &ConstraintSet) -> Option<((usize, usize), i8)> {
+
+    let board = Board::from_matrix(matrix);
+    let size = board.size();
+
+    let open: Vec<((usize, usize), Vec<i8>)> = (0..size)
+        .flat_map(|row| (0..size).map(move |col| (row, col)))
+        .filter(|&(row, col)| board.get(row, col) == 0)
+        .map(|pos| (pos, (1..=size as i8).filter(|&value| constraints.permits(&board, value, pos)).collect::<Vec<i8>>()))
+        .filter(|(_, values)| !values.is_empty())
+        .collect();
+
+    if open.is_empty() {return None}
+
+    let (pos, values) = &open[rng().random_range(0..open.len())];
+
+    Some((*pos, values[rng().random_range(0..values.len())]))
+}
+
+/*
+    SOURCE: https://sat.inesc-id.pt/~ines/publications/aimath06.pdf
+    Generates 3(n^2)
+    Uses DIMACS CNF representation https://people.sc.fsu.edu/~jburkardt/data/cnf/cnf.html
+*/
+
+// Board digits are 1-based, SAT value indices 0-based: 1 <-> 0, ..., size <-> size - 1.
+pub fn value_to_index(value: i8) -> usize {
+    debug_assert!(value >= 1, "blank or negative cell value {}", value);
+    (value - 1) as usize
+}
+
+pub fn index_to_value(n: usize) -> i8 {
+    (n + 1) as i8
+}
+
+fn lit_from_indx(row: usize, col: usize, n: usize, size: usize) -> Lit {
+    // Varisat uses 0-based var indices; `true` means positive literal.
+    /*
+        We need to create an index that is unique, dense and calculated in O(1) for each matrix cell regardless of its value.
+
+        Since n has the same range of values of row and col, I decided to treat the matrix as a 3d-array (cube) with N1=N2=N3= size.
+
+        This allows to use general array address calculation https://en.wikipedia.org/wiki/Row-_and_column-major_order
+     */
+
+    Lit::from_index(n + size * (col + size * row), true)
+
+}
+
+/// Build CNF for Sudoku with:
+///  - ALO per cell
+///  - AMO per row/col/block (for each number)
+pub fn sudoku_to_sat(matrix: &[Vec<i8>]) -> CnfFormula {
+    sudoku_to_sat_with(matrix, &ConstraintSet::default())
+}
+
+/// Same as `sudoku_to_sat`, plus the clauses of any extra `constraints`.
+pub fn sudoku_to_sat_with(matrix: &[Vec<i8>], constraints: &ConstraintSet) -> CnfFormula {
+    sudoku_to_sat_encoded(matrix, constraints, Encoding::Minimal)
+}
+
+/// Builds the CNF using the given `encoding` (see `Encoding`).
+pub fn sudoku_to_sat_encoded(matrix: &[Vec<i8>], constraints: &ConstraintSet, encoding: Encoding) -> CnfFormula {
+    sudoku_to_sat_clues(matrix, constraints, encoding, ClueEncoding::default())
+}
+
+/// Same as `sudoku_to_sat_encoded`, choosing how pre-filled cells are encoded (see `ClueEncoding`).
+pub fn sudoku_to_sat_clues(matrix: &[Vec<i8>], constraints: &ConstraintSet, encoding: Encoding, clues: ClueEncoding) -> CnfFormula {
+
+    let mut formula = CnfFormula::new();
+
+    for (_, group) in sat_clause_groups(matrix, constraints, encoding, clues) {
+        for clause in group.iter() {
+            formula.add_clause(clause);
+        }
+    }
+
+    formula
+}
+
+/*
+    The formula built by sudoku_to_sat_clues, one part per kind of clause, in formula order.
+    Counts shown for a formula are read from these parts, so they can't drift from the encoder.
+*/
+fn sat_clause_groups(matrix: &[Vec<i8>], constraints: &ConstraintSet, encoding: Encoding, clues: ClueEncoding) -> Vec<(&'static str, CnfFormula)> {
+
+    let size = matrix.len();
+
+    // 1) Each cell has AT LEAST ONE number
+    let mut cell_alo = CnfFormula::new();
+    for r in 0..size {
+        for c in 0..size {
+            let mut clause: Vec<Lit> = Vec::with_capacity(size);
+            for n in 0..size {
+                clause.push(lit_from_indx(r, c, n, size));
+            }
+            cell_alo.add_clause(&clause);
+        }
+    }
+
+    // 2-4) Each number appears at most once in each row, column and sub-grid (or jigsaw region)
+    let rows: Vec<Vec<(usize, usize)>> = (0..size).map(|r| (0..size).map(|c| (r, c)).collect()).collect();
+    let cols: Vec<Vec<(usize, usize)>> = (0..size).map(|c| (0..size).map(|r| (r, c)).collect()).collect();
+    let blocks = constraints.blocks(size);
+
+    // Auxiliary variables (sequential and log encodings) are numbered after the size^3 cell variables.
+    let mut next_aux = size.pow(3);
+    let mut amo = |units: &[Vec<(usize, usize)>]| {
+        let mut formula = CnfFormula::new();
+        add_amo_clauses(&mut formula, size, units, encoding, &mut next_aux);
+        formula
+    };
+
+    let mut groups = vec![
+        ("cell ALO", cell_alo),
+        ("row AMO", amo(&rows)),
+        ("column AMO", amo(&cols)),
+        ("block AMO", amo(&blocks)),
+    ];
+
+    if encoding == Encoding::Extended {
+        let mut extended = CnfFormula::new();
+        add_extended_clauses(&mut extended, size, &blocks);
+        groups.push(("extended", extended));
+    }
+
+    // 5) Pre-filled cells clauses
+    let mut clue_clauses = CnfFormula::new();
+    for (r, row) in matrix.iter().enumerate() {
+        for (c, &val) in row.iter().enumerate() {
+            if val != 0 {
+                let n = value_to_index(val);
+                clue_clauses.add_clause(&[lit_from_indx(r, c, n, size)]); // unit clause
+
+                if clues == ClueEncoding::Full {
+                    for other in (0..size).filter(|&m| m != n) {
+                        clue_clauses.add_clause(&[!lit_from_indx(r, c, other, size)]);
+                    }
+                }
+            }
+        }
+    }
+    groups.push(("clue", clue_clauses));
+
+    // 6) Variant rules
+    let mut variant = CnfFormula::new();
+    constraints.add_clauses(&mut variant, size);
+    groups.push(("variant", variant));
+
+    groups
+}
+
+// At most one cell of each unit holds each number, with the AMO flavour of `encoding`.
+fn add_amo_clauses(formula: &mut CnfFormula, size: usize, units: &[Vec<(usize, usize)>], encoding: Encoding, next_aux: &mut usize) {
+    for unit in units {
+        for n in 0..size {
+            let cells: Vec<Lit> = unit.iter().map(|&(r, c)| lit_from_indx(r, c, n, size)).collect();
+
+            match encoding {
+                Encoding::Sequential => add_sequential_amo(formula, &cells, next_aux),
+                Encoding::Log => add_log_amo(formula, &cells, next_aux),
+                Encoding::Minimal | Encoding::Extended => add_pairwise_amo(formula, &cells),
+            }
+        }
+    }
+}
+
+// Classic AMO: one binary clause per pair of cells.
+fn add_pairwise_amo(formula: &mut CnfFormula, cells: &[Lit]) {
+    for (i, &a) in cells.iter().enumerate() {
+        for &b in &cells[i + 1..] {
+            formula.add_clause(&[!a, !b]);
+        }
+    }
+}
+
+/*
+    Sequential (ladder) AMO, from Sinz, "Towards an Optimal CNF Encoding of Boolean Cardinality Constraints".
+    Auxiliary s_i means "one of the first i cells holds the number":
+    3k - 4 clauses and k - 1 new variables for k cells instead of k(k-1)/2 clauses.
+*/
+fn add_sequential_amo(formula: &mut CnfFormula, cells: &[Lit], next_aux: &mut usize) {
+
+    let aux: Vec<Lit> = (*next_aux..*next_aux + cells.len().saturating_sub(1)).map(|i| Lit::from_index(i, true)).collect();
+    *next_aux += aux.len();
+
+    for (i, &x) in cells.iter().enumerate() {
+        if i < aux.len() {
+            formula.add_clause(&[!x, aux[i]]); // x_i -> s_i
+        }
+        if i > 0 {
+            formula.add_clause(&[!x, !aux[i - 1]]); // x_i -> none before it
+            if i < aux.len() {
+                formula.add_clause(&[!aux[i - 1], aux[i]]); // s_(i-1) -> s_i
+            }
+        }
+    }
+}
+
+/*
+    Log (binary) AMO: ceil(log2 k) auxiliary bits spell the index of the chosen cell, so two cells can't both hold
+    the number without the bits disagreeing. k * ceil(log2 k) clauses, below pairwise once units pass 9 cells.
+*/
+fn add_log_amo(formula: &mut CnfFormula, cells: &[Lit], next_aux: &mut usize) {
+
+    let bits = cells.len().next_power_of_two().trailing_zeros() as usize;
+    let aux: Vec<Lit> = (*next_aux..*next_aux + bits).map(|i| Lit::from_index(i, true)).collect();
+    *next_aux += bits;
+
+    for (i, &x) in cells.iter().enumerate() {
+        for (bit, &b) in aux.iter().enumerate() {
+            formula.add_clause(&[!x, if (i >> bit) & 1 == 1 {b} else {!b}]); // x_i -> bit of i
+        }
+    }
+}
+
+/*
+    Redundant clauses of the extended encoding (same paper as above):
+     - AMO per cell
+     - ALO per row/col/block (for each number)
+    They don't change the solutions but give the solver more to propagate on.
+*/
+fn add_extended_clauses(formula: &mut CnfFormula, size: usize, blocks: &[Vec<(usize, usize)>]) {
+
+    // Each cell has AT MOST ONE number
+    for r in 0..size {
+        for c in 0..size {
+            for n1 in 0..size {
+                for n2 in (n1 + 1)..size {
+                    formula.add_clause(&[!lit_from_indx(r, c, n1, size), !lit_from_indx(r, c, n2, size)]);
+                }
+            }
+        }
+    }
+
+    // Each number appears AT LEAST ONCE in each row, column and sub-grid
+    for (i, cells) in blocks.iter().enumerate() {
+        for n in 0..size {
+            let row: Vec<Lit> = (0..size).map(|c| lit_from_indx(i, c, n, size)).collect();
+            let col: Vec<Lit> = (0..size).map(|r| lit_from_indx(r, i, n, size)).collect();
+            let block: Vec<Lit> = cells.iter().map(|&(r, c)| lit_from_indx(r, c, n, size)).collect();
+
+            formula.add_clause(&row);
+            formula.add_clause(&col);
+            formula.add_clause(&block);
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Encoding {
+    #[default]
+    Minimal,    // ALO per cell, AMO per row/col/block
+    Extended,   // Minimal + AMO per cell, ALO per row/col/block
+    Sequential, // Like Minimal with ladder AMO: far fewer clauses on big boards, at the cost of auxiliary variables
+    Log,        // Like Minimal with binary AMO: fewer auxiliary variables than Sequential, a few more clauses
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FormulaStats {
+    pub variables: usize,
+    pub clauses: usize,
+}
+
+impl Encoding {
+    pub const ALL: [Encoding; 4] = [Encoding::Minimal, Encoding::Extended, Encoding::Sequential, Encoding::Log];
+
+    // Size of the formula this encoding builds for `matrix` (no extra constraints).
+    pub fn stats(self, matrix: &[Vec<i8>]) -> FormulaStats {
+        self.stats_with(matrix, &ConstraintSet::default(), ClueEncoding::default())
+    }
+
+    // Read from the formula itself: building it is the only count that can't disagree with the encoder.
+    pub fn stats_with(self, matrix: &[Vec<i8>], constraints: &ConstraintSet, clues: ClueEncoding) -> FormulaStats {
+        let formula = sudoku_to_sat_clues(matrix, constraints, self, clues);
+
+        FormulaStats {variables: formula.var_count(), clauses: formula.len()}
+    }
+}
+
+/*
+    How pre-filled cells reach the solver. With Unit, the other values of a clue cell are only ruled out
+    by propagation through the row/column/block clauses; Full states it directly, at the cost of size - 1 extra clauses per clue.
+    The GUI's choice applies to its SAT solves; `cargo bench --features bench` times both on the bundled puzzles. Unit is the default.
+*/
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClueEncoding {
+    #[default]
+    Unit, // One positive unit clause per clue
+    Full, // Plus negative units forbidding every other value in the cell
+}
+
+impl ClueEncoding {
+    pub const ALL: [ClueEncoding; 2] = [ClueEncoding::Unit, ClueEncoding::Full];
+}
+
+impl fmt::Display for ClueEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClueEncoding::Unit => write!(f, "Unit"),
+            ClueEncoding::Full => write!(f, "Full"),
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Encoding::Minimal => write!(f, "Minimal"),
+            Encoding::Extended => write!(f, "Extended"),
+            Encoding::Sequential => write!(f, "Sequential"),
+            Encoding::Log => write!(f, "Log"),
+        }
+    }
+}
+
+// One encoding's run in encoding_comparison.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncodingResult {
+    pub encoding: Encoding,
+    pub solution: Option<Vec<Vec<i8>>>,
+    pub seconds: f64, // Encoding and solving (infinite when unsolvable)
+    pub variables: usize,
+    pub clauses: usize,
+}
+
+// Solves `matrix` once with every encoding, in Encoding::ALL order, for head-to-head comparisons.
+pub fn encoding_comparison(matrix: &[Vec<i8>]) -> Vec<EncodingResult> {
+    Encoding::ALL.iter()
+        .map(|&encoding| {
+            let mut solution = matrix.to_vec();
+
+            let start = Instant::now();
+            let report = solve_sat_report(&mut solution, &ConstraintSet::default(), encoding, ClueEncoding::default());
+            let seconds = if report.solved {start.elapsed().as_secs_f64()} else {f64::INFINITY};
+
+            EncodingResult {
+                encoding,
+                solution: report.solved.then_some(solution),
+                seconds,
+                variables: report.variables,
+                clauses: report.clauses,
+            }
+        })
+        .collect()
+}
+
+/// `(a, b, ord)` requires `value(a).cmp(&value(b)) == ord`, e.g. `Ordering::Greater` for "a > b".
+pub type Inequality = ((usize, usize), (usize, usize), Ordering);
+
+/*
+    Variants that restrict more pairs of cells, see Variant::forbids. They can be combined freely.
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Variant {
+    Diagonal,        // Sudoku-X: both main diagonals hold every value once
+    AntiKing,        // Cells a king's move apart can't share a value
+    AntiKnight,      // Cells a knight's move apart can't share a value
+    AntiConsecutive, // Orthogonal neighbours can't hold consecutive values
+}
+
+impl Variant {
+    pub const ALL: [Variant; 4] = [Variant::Diagonal, Variant::AntiKing, Variant::AntiKnight, Variant::AntiConsecutive];
+
+    // Whether `a` and `b` can't sit on two peers at once.
+    pub fn forbids(self, a: i8, b: i8) -> bool {
+        match self {
+            Variant::AntiConsecutive => (a - b).abs() == 1,
+            _ => a == b,
+        }
+    }
+
+    // Cells related to `pos` under this rule, board edges excluded.
+    pub fn peers(self, size: usize, pos: (usize, usize)) -> Vec<(usize, usize)> {
+
+        let (row, col) = pos;
+
+        let offsets: &[(isize, isize)] = match self {
+            Variant::Diagonal => {
+                let mut peers = Vec::new();
+                if row == col {peers.extend((0..size).map(|i| (i, i)))}
+                if row + col == size - 1 {peers.extend((0..size).map(|i| (i, size - 1 - i)))}
+                peers.retain(|&cell| cell != pos);
+                return peers;
+            }
+            Variant::AntiKing => &[(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)],
+            Variant::AntiKnight => &[(-2, -1), (-2, 1), (-1, -2), (-1, 2), (1, -2), (1, 2), (2, -1), (2, 1)],
+            Variant::AntiConsecutive => &[(-1, 0), (0, -1), (0, 1), (1, 0)],
+        };
+
+        offsets.iter()
+            .filter_map(|&(dr, dc)| Some((row.checked_add_signed(dr)?, col.checked_add_signed(dc)?)))
+            .filter(|&(r, c)| r < size && c < size)
+            .collect()
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Variant::Diagonal => write!(f, "Diagonal (Sudoku-X)"),
+            Variant::AntiKing => write!(f, "Anti-King"),
+            Variant::AntiKnight => write!(f, "Anti-Knight"),
+            Variant::AntiConsecutive => write!(f, "Anti-Consecutive"),
+        }
+    }
+}
+
+/*
+    Rules on top of the classic row/column/block ones, consumed by both the validity checks and the SAT encoding.
+    Empty by default, so a default instance behaves exactly like plain Sudoku.
+*/
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConstraintSet {
+    pub inequalities: Vec<Inequality>,
+    pub variants: Vec<Variant>,
+    pub pencil_marks: BTreeMap<(usize, usize), Vec<i8>>, // Cells the player restricted to a few values
+    pub regions: Option<Regions>, // Jigsaw regions replacing the boxes, ignored on boards of another size
+}
+
+impl ConstraintSet {
+    pub fn has(&self, variant: Variant) -> bool {
+        self.variants.contains(&variant)
+    }
+
+    pub fn set_variant(&mut self, variant: Variant, enabled: bool) {
+        self.variants.retain(|&v| v != variant);
+        if enabled {self.variants.push(variant)}
+    }
+
+    // An empty list lifts the restriction rather than making the cell unfillable.
+    pub fn set_pencil_marks(&mut self, pos: (usize, usize), marks: Vec<i8>) {
+        if marks.is_empty() {
+            self.pencil_marks.remove(&pos);
+        } else {
+            self.pencil_marks.insert(pos, marks);
+        }
+    }
+
+    fn regions_for(&self, size: usize) -> Option<&Regions> {
+        self.regions.as_ref().filter(|regions| regions.size() == size)
+    }
+
+    // The units that must hold every value once besides rows and columns: the boxes, or the jigsaw regions.
+    pub fn blocks(&self, size: usize) -> Vec<Vec<(usize, usize)>> {
+        match self.regions_for(size) {
+            Some(regions) => regions.cells().to_vec(),
+            None => units(size).split_off(2 * size),
+        }
+    }
+
+    // Index in `blocks` of the block (or region) holding `pos`.
+    pub fn block_of(&self, size: usize, pos: (usize, usize)) -> usize {
+        match self.regions_for(size) {
+            Some(regions) => regions.region_of(pos),
+            None => pos.0 / size.isqrt() * size.isqrt() + pos.1 / size.isqrt(),
+        }
+    }
+
+    // Every rule at `pos`: row, column, block (or region) and the extra rules.
+    pub fn permits(&self, board: &Board, value: i8, pos: (usize, usize)) -> bool {
+        let fits_block = match self.regions_for(board.size()) {
+            Some(regions) => board.fits_lines(value, pos)
+                && regions.region_cells(pos).iter().all(|&(row, col)| (row, col) == pos || board.get(row, col) != value),
+            None => board.is_value_valid(value, pos),
+        };
+
+        fits_block && self.allows(board, value, pos)
+    }
+
+    // Checks only the extra rules; blank neighbours never reject a value.
+    pub fn allows(&self, board: &Board, value: i8, pos: (usize, usize)) -> bool {
+
+        if let Some(marks) = self.pencil_marks.get(&pos)
+            && value != 0 && !marks.contains(&value) {
+            return false;
+        }
+
+        for &(a, b, ord) in &self.inequalities {
+            let (other, expected) = if a == pos {(b, ord)} else if b == pos {(a, ord.reverse())} else {continue};
+
+            let other_value = board.get(other.0, other.1);
+            if other_value != 0 && value.cmp(&other_value) != expected {return false}
+        }
+
+        self.variants.iter().all(|&variant| {
+            variant.peers(board.size(), pos)
+                .into_iter()
+                .all(|(row, col)| board.get(row, col) == 0 || !variant.forbids(value, board.get(row, col)))
+        })
+    }
+
+    pub fn add_clauses(&self, formula: &mut CnfFormula, size: usize) {
+
+        // Inequalities: forbid every pair of values that violates the ordering
+        for &(a, b, ord) in &self.inequalities {
+            for va in 0..size {
+                for vb in 0..size {
+                    if va.cmp(&vb) != ord {
+                        let x = lit_from_indx(a.0, a.1, va, size);
+                        let y = lit_from_indx(b.0, b.1, vb, size);
+                        formula.add_clause(&[!x, !y]);
+                    }
+                }
+            }
+        }
+
+        // Variants: AMO for each number on every extra pair
+        for (a, b) in self.variant_pairs(size) {
+            for n in 0..size {
+                formula.add_clause(&[!lit_from_indx(a.0, a.1, n, size), !lit_from_indx(b.0, b.1, n, size)]);
+            }
+        }
+
+        // Anti-consecutive: forbid n next to n + 1, both ways round
+        if self.has(Variant::AntiConsecutive) {
+            for (a, b) in orthogonal_pairs(size) {
+                for n in 1..size {
+                    formula.add_clause(&[!lit_from_indx(a.0, a.1, n - 1, size), !lit_from_indx(b.0, b.1, n, size)]);
+                    formula.add_clause(&[!lit_from_indx(a.0, a.1, n, size), !lit_from_indx(b.0, b.1, n - 1, size)]);
+                }
+            }
+        }
+
+        // Pencil marks: every value left out of a cell's marks is false there
+        for (&(row, col), marks) in &self.pencil_marks {
+            for n in 0..size {
+                if !marks.contains(&index_to_value(n)) {
+                    formula.add_clause(&[!lit_from_indx(row, col, n, size)]);
+                }
+            }
+        }
+    }
+
+    // Number of clauses add_clauses produces, without building them.
+    pub fn clause_count(&self, size: usize) -> usize {
+        let inequalities: usize = self.inequalities.iter()
+            .map(|&(_, _, ord)| (0..size).flat_map(|va| (0..size).map(move |vb| va.cmp(&vb))).filter(|&o| o != ord).count())
+            .sum();
+
+        let consecutive = if self.has(Variant::AntiConsecutive) {orthogonal_pairs(size).len() * 2 * size.saturating_sub(1)} else {0};
+
+        let marks: usize = self.pencil_marks.values()
+            .map(|marks| (1..=size as i8).filter(|value| !marks.contains(value)).count())
+            .sum();
+
+        inequalities + self.variant_pairs(size).len() * size + consecutive + marks
+    }
+
+    /*
+        Pairs of cells the variants keep apart, each listed once even when several variants agree.
+        Pairs in the same row, column or block (the jigsaw region, if any) are left out: the classic clauses already cover them.
+    */
+    fn variant_pairs(&self, size: usize) -> BTreeSet<((usize, usize), (usize, usize))> {
+
+        let mut block_of = vec![0; size * size];
+        for (block, cells) in self.blocks(size).iter().enumerate() {
+            for &(row, col) in cells {
+                block_of[row * size + col] = block;
+            }
+        }
+
+        let same_unit = |a: (usize, usize), b: (usize, usize)| a.0 == b.0 || a.1 == b.1
+            || block_of[a.0 * size + a.1] == block_of[b.0 * size + b.1];
+
+        let mut pairs = BTreeSet::new();
+
+        // Anti-consecutive pairs may share a value, they get their own clauses.
+        for &variant in self.variants.iter().filter(|&&v| v != Variant::AntiConsecutive) {
+            for row in 0..size {
+                for col in 0..size {
+                    for other in variant.peers(size, (row, col)) {
+                        if (row, col) < other && !same_unit((row, col), other) {
+                            pairs.insert(((row, col), other));
+                        }
+                    }
                 }
             }
         }
+
+        pairs
+    }
+}
+
+// Horizontally or vertically adjacent cells, each pair once.
+fn orthogonal_pairs(size: usize) -> Vec<((usize, usize), (usize, usize))> {
+    let mut pairs = Vec::new();
+
+    for row in 0..size {
+        for col in 0..size {
+            if col + 1 < size {pairs.push(((row, col), (row, col + 1)))}
+            if row + 1 < size {pairs.push(((row, col), (row + 1, col)))}
+        }
+    }
+
+    pairs
+}
+
+/*
+    Flat row-major storage for a square grid.
+    A single allocation keeps cells contiguous, which matters on 16x16/25x25 boards where the solvers
+    spend most of their time scanning rows, columns and blocks.
+*/
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Board {
+    size: usize,
+    cells: Vec<i8>,
+}
+
+impl Board {
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            cells: vec![0; size * size],
+        }
+    }
+
+    pub fn from_matrix(matrix: &[Vec<i8>]) -> Self {
+        Self {
+            size: matrix.len(),
+            cells: matrix.iter().flatten().copied().collect(),
+        }
+    }
+
+    pub fn to_matrix(&self) -> Vec<Vec<i8>> {
+        self.cells.chunks(self.size.max(1)).map(|row| row.to_vec()).collect()
+    }
+
+    // Copies the board back into an existing nested matrix of the same size (avoids reallocating GUI data).
+    pub fn write_to(&self, matrix: &mut [Vec<i8>]) {
+        for (row, cells) in matrix.iter_mut().zip(self.cells.chunks(self.size.max(1))) {
+            row.copy_from_slice(cells);
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> i8 {
+        self.cells[row * self.size + col]
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, value: i8) {
+        self.cells[row * self.size + col] = value;
+    }
+
+    // Legal values for an empty cell (filled cells have none).
+    pub fn candidates(&self, pos: (usize, usize)) -> Vec<i8> {
+        if self.get(pos.0, pos.1) != 0 {return Vec::new()}
+
+        (1..=self.size as i8).filter(|&v| self.is_value_valid(v, pos)).collect()
+    }
+
+    pub fn is_value_valid(&self, value: i8, pos: (usize, usize)) -> bool {
+
+        if !self.fits_lines(value, pos) {return false}
+
+        let sub_size = self.size.isqrt();
+
+        let row_sub = pos.0 - (pos.0 % sub_size);
+        let col_sub = pos.1 - (pos.1 % sub_size);
+
+        for row in 0..sub_size{
+            for col in 0..sub_size {
+                if row + row_sub == pos.0 && col + col_sub == pos.1 {continue}
+
+                if self.get(row + row_sub, col + col_sub) == value {return false}
+            }
+        }
+        true
+    }
+
+    // The range, row and column part of is_value_valid, for layouts whose blocks aren't boxes.
+    pub fn fits_lines(&self, value: i8, pos: (usize, usize)) -> bool {
+
+        let size = self.size;
+
+        // Also rejects out of range values, e.g. 2 on a 1x1 board where isqrt gives no block to catch it.
+        if value < 1 || value as usize > size {return false;}
+
+        for i in 0..size { // Need to jump current pos for iterations before backtrack
+            if (self.get(pos.0, i) == value && i != pos.1) || (self.get(i, pos.1) == value && i != pos.0) {return false};
+        }
+
+        true
+    }
+}
+
+impl From<&[Vec<i8>]> for Board {
+    fn from(matrix: &[Vec<i8>]) -> Self {
+        Self::from_matrix(matrix)
+    }
+}
+
+impl From<&Board> for Vec<Vec<i8>> {
+    fn from(board: &Board) -> Self {
+        board.to_matrix()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUZZLE: &str = "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+
+    fn puzzle() -> Vec<Vec<i8>> {
+        parse_puzzle(PUZZLE).unwrap()
+    }
+
+    #[test]
+    fn backtracking_rejects_conflicting_clues() {
+        let mut matrix = puzzle();
+        matrix[0][2] = 5; // Same row as the 5 at (0, 0)
+
+        assert!(!solve_backtracking(&mut matrix));
+        assert_eq!(matrix[0][2], 5);
+        assert_eq!(BacktrackStepper::new(Board::from_matrix(&matrix), ConstraintSet::default()).step(), BacktrackStep::Unsolvable);
+    }
+
+    #[test]
+    fn backtracking_returns_a_complete_solution() {
+        let mut matrix = puzzle();
+
+        assert!(solve_backtracking(&mut matrix));
+        assert!(is_complete_solution(&matrix));
+    }
+
+    #[test]
+    fn variant_pairs_follow_the_regions() {
+        // (0, 1) and (1, 0) share the top-left box but not a region
+        let mut constraints = ConstraintSet {regions: Some(Regions::from_map("AAAB CABB CCDB CDDD").unwrap()), ..Default::default()};
+        constraints.set_variant(Variant::AntiKing, true);
+
+        assert!(constraints.variant_pairs(4).contains(&((0, 1), (1, 0))));
+
+        let clause = [!lit_from_indx(0, 1, 0, 4), !lit_from_indx(1, 0, 0, 4)];
+        let formula = sudoku_to_sat_with(&vec![vec![0; 4]; 4], &constraints);
+        assert!(normalized_clauses(&formula).contains(&clause.to_vec()));
+
+        constraints.regions = None;
+        assert!(!constraints.variant_pairs(4).contains(&((0, 1), (1, 0))));
+    }
+
+    #[test]
+    fn solutions_honor_the_constraints() {
+        let empty = vec![vec![0; 4]; 4];
+        let mut constraints = ConstraintSet::default();
+        constraints.set_variant(Variant::Diagonal, true);
+
+        let solutions = collect_solutions_with(&empty, &constraints, 1000);
+
+        assert_eq!(count_solutions(&empty, 1000), 288);
+        assert!(!solutions.is_empty() && solutions.len() < 288);
+        assert_eq!(count_solutions_with(&empty, &constraints, 1000), solutions.len());
+        assert!(solutions.iter().all(|solution| is_complete_solution_with(solution, &constraints)));
+    }
+
+    #[test]
+    fn suggested_moves_respect_the_constraints() {
+        let mut matrix = parse_puzzle("1234341221434321").unwrap();
+        matrix[0][0] = 0;
+
+        let mut constraints = ConstraintSet::default();
+        constraints.set_pencil_marks((0, 0), vec![2]);
+
+        assert_eq!(suggest_move(&matrix), Some(((0, 0), 1)));
+        assert_eq!(suggest_move_with(&matrix, &constraints), None);
+    }
+
+    #[test]
+    fn solve_region_fills_a_single_block() {
+        let matrix = puzzle();
+        let block = solve_region(&matrix, &ConstraintSet::default(), 4).unwrap();
+
+        let mut solution = puzzle();
+        assert!(solve_sat(&mut solution));
+
+        for (row, col) in diff_cells(&matrix, &block) {
+            assert_eq!((row / 3, col / 3), (1, 1));
+            assert_eq!(block[row][col], solution[row][col]);
+        }
+        assert!((3..6).all(|row| (3..6).all(|col| block[row][col] != 0)));
+    }
+
+    #[test]
+    fn solve_region_follows_the_jigsaw_regions() {
+        let constraints = ConstraintSet {regions: Some(Regions::from_map("AAAB CABB CCDB CDDD").unwrap()), ..Default::default()};
+        let empty = vec![vec![0; 4]; 4];
+
+        let block = solve_region(&empty, &constraints, constraints.block_of(4, (1, 0))).unwrap();
+
+        assert_eq!(diff_cells(&empty, &block), vec![(1, 0), (2, 0), (2, 1), (3, 0)]);
+    }
+
+    #[test]
+    fn is_value_valid_agrees_with_the_board() {
+        let matrix = puzzle();
+        let board = Board::from_matrix(&matrix);
+
+        for (row, col) in (0..9).flat_map(|row| (0..9).map(move |col| (row, col))) {
+            for value in 0..=10 {
+                assert_eq!(is_value_valid(&matrix, value, (row, col)), board.is_value_valid(value, (row, col)), "{} at ({}, {})", value, row, col);
+            }
+        }
     }
 
-    // 3) Each number appears at most once in each column
-    for c in 0..size {
-        for n in 0..size {
-            for r1 in 0..size {
-                for r2 in (r1 + 1)..size {
-                    let a = lit_from_indx(r1, c, n, size);
-                    let b = lit_from_indx(r2, c, n, size);
-                    formula.add_clause(&[!a, !b]);
+    #[test]
+    fn solve_capped_refuses_boards_above_the_cap() {
+        let mut matrix = vec![vec![0; 16]; 16];
+
+        assert_eq!(solve_capped(&mut matrix, Algorithm::Sat, &ConstraintSet::default(), DEFAULT_FALLBACK_STEPS, ClueEncoding::default(), 9), Err(SizeError::TooLarge {size: 16, max: 9}));
+        assert_eq!(empty_count(&matrix), 256);
+
+        let mut matrix = puzzle();
+        assert_eq!(solve_capped(&mut matrix, Algorithm::Sat, &ConstraintSet::default(), DEFAULT_FALLBACK_STEPS, ClueEncoding::default(), 9), Ok(Some(SolvedBy::Sat)));
+    }
+
+    #[test]
+    fn validate_and_solve_stops_at_conflicting_clues() {
+        let mut clean = puzzle();
+        assert_eq!(validate_and_solve(&mut clean, Algorithm::Sat, &ConstraintSet::default(), DEFAULT_FALLBACK_STEPS, ClueEncoding::default()), Ok(SolvedBy::Sat));
+        assert!(is_complete_solution(&clean));
+
+        let mut conflicting = puzzle();
+        conflicting[0][2] = 5;
+        let before = conflicting.clone();
+
+        assert_eq!(validate_and_solve(&mut conflicting, Algorithm::Sat, &ConstraintSet::default(), DEFAULT_FALLBACK_STEPS, ClueEncoding::default()),
+            Err(SolveStopped::Conflicts(vec![(0, 0), (0, 2)])));
+        assert_eq!(conflicting, before);
+    }
+
+    #[test]
+    fn encoding_stats_match_the_built_formulas() {
+        for matrix in [parse_puzzle("1000003000020004").unwrap(), puzzle()] {
+            for encoding in Encoding::ALL {
+                for clues in ClueEncoding::ALL {
+                    let formula = sudoku_to_sat_clues(&matrix, &ConstraintSet::default(), encoding, clues);
+                    let stats = encoding.stats_with(&matrix, &ConstraintSet::default(), clues);
+
+                    assert_eq!(stats, FormulaStats {variables: formula.var_count(), clauses: formula.len()}, "{} {}", encoding, clues);
                 }
             }
+            assert_eq!(sat_clause_count(&matrix), sudoku_to_sat(&matrix).len());
         }
+
+        // 9x9 with unit clues: 81 ALO + 3 * 81 * 36 pairwise AMO + 30 clues
+        assert_eq!(Encoding::Minimal.stats(&puzzle()), FormulaStats {variables: 729, clauses: 8859});
+
+        let (log, sequential) = (Encoding::Log.stats(&puzzle()), Encoding::Sequential.stats(&puzzle()));
+        assert_eq!(log.variables, 729 + 27 * 9 * 4);
+        assert!(log.variables < sequential.variables);
+
+        // Binary AMO only pays off past 9 cells per unit: 16 * 4 clauses against 16 * 15 / 2
+        let empty = vec![vec![0; 16]; 16];
+        assert!(Encoding::Log.stats(&empty).clauses < Encoding::Minimal.stats(&empty).clauses);
     }
 
-    // 4) Each number appears at most once in each 3x3 sub-grid
-    for br in 0..sub_size {
-        for bc in 0..sub_size {
-            for n in 0..size {
-                // flatten block coords 0..size-1 -> (dr, dc)
-                for i in 0..size {
-                    for j in (i + 1)..size {
-                        let r1 = br * sub_size + (i / sub_size);
-                        let c1 = bc * sub_size + (i % sub_size);
-                        let r2 = br * sub_size + (j / sub_size);
-                        let c2 = bc * sub_size + (j % sub_size);
-                        let a = lit_from_indx(r1, c1, n, size);
-                        let b = lit_from_indx(r2, c2, n, size);
-                        formula.add_clause(&[!a, !b]);
-                    }
+    #[test]
+    fn every_encoding_finds_the_same_solution() {
+        let mut expected = puzzle();
+        assert!(solve_sat(&mut expected));
+
+        for encoding in Encoding::ALL {
+            let mut matrix = puzzle();
+            assert!(solve_sat_encoded(&mut matrix, &ConstraintSet::default(), encoding), "{}", encoding);
+            assert_eq!(matrix, expected, "{}", encoding);
+        }
+    }
+
+    #[test]
+    fn clause_breakdown_sums_to_the_formula() {
+        let mut constraints = ConstraintSet {regions: Some(Regions::from_map("AAAB CABB CCDB CDDD").unwrap()), ..Default::default()};
+        constraints.set_variant(Variant::AntiKing, true);
+        constraints.set_pencil_marks((3, 3), vec![1, 2]);
+
+        for (matrix, constraints) in [(puzzle(), ConstraintSet::default()), (vec![vec![0; 4]; 4], constraints)] {
+            let breakdown = sat_clause_breakdown(&matrix, &constraints);
+            let total: usize = breakdown.iter().map(|&(_, clauses)| clauses).sum();
+
+            assert_eq!(total, sudoku_to_sat_with(&matrix, &constraints).len());
+            assert!(get_sat_summary(&matrix, &constraints).starts_with(&format!("p cnf {} {}\n", matrix.len().pow(3), total)));
+        }
+    }
+
+    #[test]
+    fn clue_encodings_agree_and_full_is_larger() {
+        let (mut unit, mut full) = (puzzle(), puzzle());
+
+        assert!(solve_sat_clues(&mut unit, &ConstraintSet::default(), ClueEncoding::Unit));
+        assert!(solve_sat_clues(&mut full, &ConstraintSet::default(), ClueEncoding::Full));
+        assert_eq!(unit, full);
+
+        let clauses = |clues| sudoku_to_sat_clues(&puzzle(), &ConstraintSet::default(), Encoding::Minimal, clues).len();
+        assert_eq!(clauses(ClueEncoding::Full), clauses(ClueEncoding::Unit) + 30 * 8);
+
+        let mut solved = puzzle();
+        assert_eq!(solve_reporting(&mut solved, Algorithm::Sat, &ConstraintSet::default(), DEFAULT_FALLBACK_STEPS, ClueEncoding::Full), Some(SolvedBy::Sat));
+        assert_eq!(solved, unit);
+    }
+
+    #[test]
+    fn malformed_boards_have_no_solutions() {
+        let ragged = vec![vec![0; 4], vec![0; 3], vec![0; 4], vec![0; 4]];
+
+        for matrix in [vec![vec![0; 3]; 3], ragged, Vec::new()] {
+            assert_eq!(count_solutions(&matrix, 10), 0);
+            assert!(collect_solutions(&matrix, 10).is_empty());
+            assert_eq!(for_each_solution(&matrix, &ConstraintSet::default(), 10, |_| panic!("no solution expected")), 0);
+        }
+    }
+
+    #[test]
+    fn typed_letters_enter_values_past_nine() {
+        assert_eq!(value_from_char('7', 9), Some(7));
+        assert_eq!(value_from_char('0', 9), None);
+        assert_eq!(value_from_char('a', 9), None);
+        assert_eq!(value_from_char('a', 16), Some(10));
+        assert_eq!(value_from_char('G', 16), Some(16));
+        assert_eq!(value_from_char('H', 16), None);
+        assert_eq!(value_from_char('P', 25), Some(25));
+        assert_eq!(value_from_char('?', 25), None);
+    }
+
+    #[test]
+    fn board_accessors_agree_with_the_nested_rows() {
+        let matrix = puzzle();
+        let mut board = Board::from_matrix(&matrix);
+
+        assert_eq!(board.size(), 9);
+        assert!((0..9).all(|row| (0..9).all(|col| board.get(row, col) == matrix[row][col])));
+        assert_eq!(board.to_matrix(), matrix);
+
+        board.set(0, 2, 4);
+        let mut written = matrix.clone();
+        board.write_to(&mut written);
+
+        assert_eq!(written[0][2], 4);
+        assert_eq!(diff_cells(&matrix, &written), vec![(0, 2)]);
+        assert_eq!(Board::new(4).to_matrix(), vec![vec![0; 4]; 4]);
+    }
+
+    #[test]
+    fn an_inequality_forces_the_assignment() {
+        // 1234/3412/2143/4321 with a swappable rectangle blanked: the 1s and 2s in the first two columns of rows 0 and 2 can trade places
+        let matrix = parse_puzzle("0034341200434321").unwrap();
+        let constraints = ConstraintSet {inequalities: vec![((0, 0), (0, 1), Ordering::Greater)], ..Default::default()};
+
+        assert_eq!(count_solutions(&matrix, 10), 2);
+        assert_eq!(count_solutions_with(&matrix, &constraints, 10), 1);
+
+        let mut solved = matrix.clone();
+        assert!(solve_sat_with(&mut solved, &constraints));
+        assert_eq!((solved[0][0], solved[0][1]), (2, 1));
+
+        let mut board = Board::from_matrix(&matrix);
+        board.set(0, 1, 2);
+        assert!(!constraints.allows(&board, 1, (0, 0)));
+        assert!(constraints.allows(&board, 3, (0, 0)));
+    }
+
+    #[test]
+    fn a_1x1_board_goes_through_the_whole_pipeline() {
+        let empty = vec![vec![0]];
+
+        assert!(is_value_valid(&empty, 1, (0, 0)));
+        assert!(!is_value_valid(&empty, 2, (0, 0)));
+        assert!(!Board::from_matrix(&empty).is_value_valid(2, (0, 0)));
+        assert_ne!(sudoku_to_sat(&empty).len(), 0);
+        assert_eq!(count_solutions(&empty, 10), 1);
+
+        let mut matrix = empty.clone();
+        assert!(solve_backtracking(&mut matrix));
+        assert_eq!(matrix, [[1]]);
+
+        let mut matrix = empty.clone();
+        assert!(solve_sat(&mut matrix));
+        assert_eq!(matrix, [[1]]);
+
+        let mut matrix = empty.clone();
+        generate_random_matrix(&mut matrix, 1);
+        assert_eq!(matrix, [[1]]);
+        assert_eq!(random_solution(1), [[1]]);
+        assert_eq!(count_solutions(&generate_seeded(1, 7), 10), 1);
+    }
+
+    #[test]
+    fn bounded_decode_stays_short_above_the_limit() {
+        let empty = vec![vec![0; 25]; 25];
+        let text = get_sat_decode_bounded(&empty, &ConstraintSet::default(), 1000);
+
+        assert!(text.len() < 200);
+        assert!(text.starts_with(&format!("p cnf {} {}", 25usize.pow(3), sat_clause_count(&empty))));
+
+        let small = parse_puzzle("1204301221004301").unwrap();
+        assert_eq!(get_sat_decode_bounded(&small, &ConstraintSet::default(), 1000), get_sat_decode(&small));
+    }
+
+    #[test]
+    fn swapping_digits_twice_gives_the_original() {
+        let mut matrix = puzzle();
+        assert!(swap_digits(&mut matrix, 5, 9));
+        assert_eq!((matrix[0][0], matrix[4][8]), (9, 1));
+        assert_ne!(matrix, puzzle());
+
+        assert!(swap_digits(&mut matrix, 9, 5));
+        assert_eq!(matrix, puzzle());
+
+        assert!(!swap_digits(&mut matrix, 0, 5));
+        assert!(!swap_digits(&mut matrix, 5, 10));
+    }
+
+    #[test]
+    fn solve_dispatches_to_the_chosen_algorithm() {
+        let classic = ConstraintSet::default();
+        let expected = [
+            (Algorithm::Backtracking, SolvedBy::Backtracking),
+            (Algorithm::Sat, SolvedBy::Sat),
+            (Algorithm::Auto, SolvedBy::Backtracking), // Easy puzzle: solved well before the fallback
+            (Algorithm::Dlx, SolvedBy::Dlx),
+        ];
+
+        for (algorithm, solved_by) in expected {
+            let mut matrix = puzzle();
+            assert_eq!(solve_reporting(&mut matrix, algorithm, &classic, DEFAULT_FALLBACK_STEPS, ClueEncoding::default()), Some(solved_by));
+            assert!(is_complete_solution(&matrix));
+        }
+
+        let mut diagonal = ConstraintSet::default();
+        diagonal.set_variant(Variant::Diagonal, true);
+        let mut matrix = vec![vec![0; 4]; 4];
+        assert_eq!(solve_reporting(&mut matrix, Algorithm::Dlx, &diagonal, DEFAULT_FALLBACK_STEPS, ClueEncoding::default()), Some(SolvedBy::Sat));
+    }
+
+    #[test]
+    fn affected_cells_cover_row_column_and_block() {
+        for pos in [(4, 4), (0, 0)] {
+            let mut peers = affected_cells(9, pos, &ConstraintSet::default());
+            peers.sort();
+            peers.dedup();
+
+            let expected: Vec<(usize, usize)> = (0..9).flat_map(|row| (0..9).map(move |col| (row, col)))
+                .filter(|&cell| cell != pos && (cell.0 == pos.0 || cell.1 == pos.1 || (cell.0 / 3, cell.1 / 3) == (pos.0 / 3, pos.1 / 3)))
+                .collect();
+
+            assert_eq!(affected_cells(9, pos, &ConstraintSet::default()).len(), 20);
+            assert_eq!(peers, expected);
+        }
+
+        let corner = affected_cells(9, (0, 0), &ConstraintSet::default());
+        assert!(corner.contains(&(2, 2)) && corner.contains(&(8, 0)) && corner.contains(&(0, 8)));
+        assert!(!corner.contains(&(3, 3)));
+
+        // On this jigsaw board (1, 1) shares the region of (0, 0), while (0, 1) and (1, 0) no longer share a block
+        let jigsaw = ConstraintSet {regions: Some(Regions::from_map("AAAB CABB CCDB CDDD").unwrap()), ..Default::default()};
+        let mut peers = affected_cells(4, (0, 0), &jigsaw);
+        peers.sort();
+
+        assert_eq!(peers, [(0, 1), (0, 2), (0, 3), (1, 0), (1, 1), (2, 0), (3, 0)]);
+        assert!(!affected_cells(4, (1, 0), &jigsaw).contains(&(0, 1)));
+    }
+
+    #[test]
+    fn slow_backtracking_falls_back_to_sat() {
+        // Built against naive backtracking: its first row is 987654321 and the clues only allow it late
+        let hard = "000000000000003085001020000000507000004000100090000000500000073002010000000040009";
+        let mut matrix = parse_puzzle(hard).unwrap();
+
+        let solved_by = solve_reporting(&mut matrix, Algorithm::Auto, &ConstraintSet::default(), 10_000, ClueEncoding::default());
+
+        assert_eq!(solved_by.map(|solver| solver.to_string()).as_deref(), Some("SAT (fallback)"));
+        assert!(is_complete_solution(&matrix));
+        assert_eq!(matrix[0], [9, 8, 7, 6, 5, 4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn re_solving_highlights_the_changed_cells() {
+        let mut first = parse_puzzle("1034341200434321").unwrap();
+        assert!(solve_sat(&mut first));
+
+        // A 2 instead of the 1 in the corner swaps the 1s and 2s of the first two columns in rows 0 and 2
+        let mut second = parse_puzzle("2034341200434321").unwrap();
+        assert!(solve_sat(&mut second));
+
+        assert_eq!(diff_cells(&first, &second), [(0, 0), (0, 1), (2, 0), (2, 1)]);
+        assert!(diff_cells(&first, &first).is_empty());
+    }
+
+    #[test]
+    fn anti_king_rejects_diagonal_neighbours() {
+        let mut constraints = ConstraintSet::default();
+        constraints.set_variant(Variant::AntiKing, true);
+
+        let mut board = Board::from_matrix(&vec![vec![0; 9]; 9]);
+        board.set(2, 2, 5);
+
+        // (3, 3) is in another block, row and column: only the king's move relates it to (2, 2)
+        assert!(board.is_value_valid(5, (3, 3)));
+        assert!(!constraints.allows(&board, 5, (3, 3)));
+        assert!(constraints.allows(&board, 5, (4, 4)));
+
+        assert_eq!(Variant::AntiKing.peers(9, (0, 0)).len(), 3);
+        assert_eq!(Variant::AntiKing.peers(9, (0, 4)).len(), 5);
+        assert_eq!(Variant::AntiKing.peers(9, (4, 4)).len(), 8);
+
+        let mut matrix = vec![vec![0; 9]; 9];
+        assert!(solve_sat_with(&mut matrix, &constraints));
+        assert!(is_complete_solution_with(&matrix, &constraints));
+        assert!((0..8).all(|row| (0..8).all(|col| matrix[row][col] != matrix[row + 1][col + 1] && matrix[row][col + 1] != matrix[row + 1][col])));
+    }
+
+    #[test]
+    fn wrong_entries_are_checked_against_the_solution() {
+        let solution = parse_puzzle("1234341221434321").unwrap();
+
+        assert!(check_against_solution(&solution, &solution).is_empty());
+
+        let mut wrong = solution.clone();
+        wrong[0][0] = 2;
+        wrong[3][2] = 1;
+        assert_eq!(check_against_solution(&wrong, &solution), [(0, 0), (3, 2)]);
+
+        // Blanks aren't mistakes, only the filled cells count
+        let mut incomplete = parse_puzzle("1204301221004301").unwrap();
+        assert!(check_against_solution(&incomplete, &solution).is_empty());
+        incomplete[1][1] = 3;
+        assert_eq!(check_against_solution(&incomplete, &solution), [(1, 1)]);
+    }
+
+    #[test]
+    fn sequential_encoding_is_smaller_at_16x16() {
+        let mut solution = vec![vec![0; 16]; 16];
+        assert!(solve_sat(&mut solution));
+
+        let mut puzzle = solution.clone();
+        for (i, value) in puzzle.iter_mut().flatten().enumerate() {
+            if i % 3 != 0 {*value = 0}
+        }
+
+        for encoding in [Encoding::Minimal, Encoding::Sequential] {
+            let mut matrix = puzzle.clone();
+            assert!(solve_sat_encoded(&mut matrix, &ConstraintSet::default(), encoding), "{}", encoding);
+            assert!(is_complete_solution(&matrix));
+            assert!(check_against_solution(&puzzle, &matrix).is_empty());
+        }
+
+        let (pairwise, sequential) = (Encoding::Minimal.stats(&puzzle), Encoding::Sequential.stats(&puzzle));
+        assert!(sequential.clauses < pairwise.clauses);
+        assert!(sequential.variables > pairwise.variables);
+    }
+
+    #[test]
+    fn the_callback_runs_once_per_solution() {
+        let mut seen: Vec<Vec<Vec<i8>>> = Vec::new();
+        let found = for_each_solution(&vec![vec![0; 4]; 4], &ConstraintSet::default(), 1000, |solution| seen.push(solution.to_vec()));
+
+        assert_eq!(found, 288);
+        assert_eq!(seen.len(), 288);
+        assert!(seen.iter().all(|solution| is_complete_solution(solution)));
+        seen.sort();
+        seen.dedup();
+        assert_eq!(seen.len(), 288);
+
+        let mut calls = 0;
+        assert_eq!(for_each_solution(&vec![vec![0; 4]; 4], &ConstraintSet::default(), 10, |_| calls += 1), 10);
+        assert_eq!(calls, 10);
+    }
+
+    #[test]
+    fn combined_variants_are_both_enforced() {
+        let mut constraints = ConstraintSet::default();
+        constraints.set_variant(Variant::Diagonal, true);
+        constraints.set_variant(Variant::AntiKnight, true);
+
+        let mut matrix = vec![vec![0; 9]; 9];
+        assert!(solve_sat_with(&mut matrix, &constraints));
+
+        let diagonal: BTreeSet<i8> = (0..9).map(|i| matrix[i][i]).collect();
+        let anti_diagonal: BTreeSet<i8> = (0..9).map(|i| matrix[i][8 - i]).collect();
+        assert_eq!((diagonal.len(), anti_diagonal.len()), (9, 9));
+        assert!((0..9).all(|row| (0..9).all(|col| {
+            Variant::AntiKnight.peers(9, (row, col)).into_iter().all(|(r, c)| matrix[r][c] != matrix[row][col])
+        })));
+
+        // Either rule alone rejects a value
+        let mut board = Board::from_matrix(&vec![vec![0; 9]; 9]);
+        board.set(0, 0, 7);
+        assert!(!constraints.allows(&board, 7, (8, 8)));
+        assert!(!constraints.allows(&board, 7, (1, 2)));
+        assert!(constraints.allows(&board, 7, (4, 5)));
+    }
+
+    #[test]
+    fn dimacs_output_matches_its_own_puzzle_only() {
+        let dimacs = get_sat_decode(&puzzle());
+        assert!(dimacs_matches_puzzle(&dimacs, &puzzle()));
+
+        // Negating one clue's unit clause
+        let clue = dimacs.lines().find(|line| line.split_whitespace().count() == 2 && !line.starts_with('p')).unwrap();
+        let tampered = dimacs.replacen(&format!("\n{}\n", clue), &format!("\n-{}\n", clue), 1);
+        assert_ne!(tampered, dimacs);
+        assert!(!dimacs_matches_puzzle(&tampered, &puzzle()));
+
+        let mut other = puzzle();
+        other[0][2] = 4;
+        assert!(!dimacs_matches_puzzle(&dimacs, &other));
+        assert!(!dimacs_matches_puzzle("not a cnf", &puzzle()));
+    }
+
+    #[test]
+    fn conflicting_values_come_from_the_cell_peers() {
+        let matrix = parse_puzzle("1204301221004301").unwrap();
+
+        // 1 and 2 in row 2, 1 in column 2 and in the bottom-right block
+        assert_eq!(conflicting_values(&matrix, (2, 2)), [1, 2]);
+        // The 4 at (0, 3) is the cell's own value, not a conflict
+        assert_eq!(conflicting_values(&matrix, (0, 3)), [1, 2]);
+        assert!(conflicting_values(&vec![vec![0; 4]; 4], (2, 2)).is_empty());
+
+        let mut constraints = ConstraintSet::default();
+        constraints.set_pencil_marks((2, 2), vec![4]);
+        assert_eq!(conflicting_values_with(&matrix, (2, 2), &constraints), [1, 2, 3]);
+    }
+
+    #[test]
+    fn anti_consecutive_rejects_adjacent_neighbours() {
+        let mut constraints = ConstraintSet::default();
+        constraints.set_variant(Variant::AntiConsecutive, true);
+
+        let mut board = Board::from_matrix(&vec![vec![0; 9]; 9]);
+        board.set(4, 4, 5);
+
+        assert!(!constraints.allows(&board, 4, (4, 5)));
+        assert!(!constraints.allows(&board, 6, (3, 4)));
+        assert!(constraints.allows(&board, 7, (4, 5)));
+        assert!(constraints.allows(&board, 4, (5, 5))); // Diagonal neighbours aren't orthogonal
+
+        let mut matrix = vec![vec![0; 9]; 9];
+        assert!(solve_sat_with(&mut matrix, &constraints));
+        assert!((0..9).all(|row| (0..8).all(|col| (matrix[row][col] - matrix[row][col + 1]).abs() != 1 && (matrix[col][row] - matrix[col + 1][row]).abs() != 1)));
+    }
+
+    #[test]
+    fn a_9x9_board_round_trips_through_an_array() {
+        let array = to_array9(&puzzle()).unwrap();
+
+        assert_eq!(array[0], [5, 3, 0, 0, 7, 0, 0, 0, 0]);
+        assert_eq!(from_array9(&array), puzzle());
+
+        assert_eq!(to_array9(&vec![vec![0; 4]; 4]), Err(DimensionError {rows: 4, cols: 4}));
+        let mut ragged = puzzle();
+        ragged[3].pop();
+        assert_eq!(to_array9(&ragged), Err(DimensionError {rows: 9, cols: 8}));
+    }
+
+    #[test]
+    fn pencil_marks_restrict_the_solution() {
+        // Two solutions, told apart by the corner
+        let matrix = parse_puzzle("0034341200434321").unwrap();
+        let mut unrestricted = matrix.clone();
+        assert!(solve_sat(&mut unrestricted));
+
+        let mut constraints = ConstraintSet::default();
+        constraints.set_pencil_marks((0, 0), vec![3 - unrestricted[0][0]]);
+
+        let mut restricted = matrix.clone();
+        assert_eq!(count_solutions_with(&matrix, &constraints, 10), 1);
+        assert!(solve_sat_with(&mut restricted, &constraints));
+        assert_eq!(restricted[0][0], 3 - unrestricted[0][0]);
+        assert_ne!(restricted, unrestricted);
+
+        constraints.set_pencil_marks((0, 0), vec![3, 4]);
+        assert!(!solve_sat_with(&mut matrix.clone(), &constraints));
+    }
+
+    #[test]
+    fn the_solve_report_counts_the_formula() {
+        for encoding in Encoding::ALL {
+            let mut matrix = puzzle();
+            let report = solve_sat_report(&mut matrix, &ConstraintSet::default(), encoding, ClueEncoding::default());
+            let stats = encoding.stats_with(&puzzle(), &ConstraintSet::default(), ClueEncoding::default());
+
+            assert!(report.solved && is_complete_solution(&matrix));
+            assert_eq!((report.variables, report.clauses), (stats.variables, stats.clauses), "{}", encoding);
+            assert!(report.literals > report.clauses && report.estimated_bytes > 0);
+        }
+
+        let mut unsolvable = parse_puzzle("1000003000020004").unwrap();
+        let report = solve_sat_report(&mut unsolvable, &ConstraintSet::default(), Encoding::Minimal, ClueEncoding::default());
+        assert!(!report.solved);
+        assert_eq!(report.clauses, sat_clause_count(&unsolvable));
+    }
+
+    #[test]
+    fn next_cell_wraps_only_when_asked() {
+        assert_eq!(next_cell(9, (0, 0), false), Some((0, 1)));
+        assert_eq!(next_cell(9, (0, 8), false), Some((1, 0)));
+        assert_eq!(next_cell(9, (8, 8), false), None);
+        assert_eq!(next_cell(9, (8, 8), true), Some((0, 0)));
+        assert_eq!(next_cell(1, (0, 0), true), Some((0, 0)));
+    }
+
+    #[test]
+    fn every_blank_style_exports_and_parses_back() {
+        let matrix = parse_puzzle("1204301221004301").unwrap();
+
+        let exported: Vec<String> = BlankStyle::ALL.into_iter().map(|style| export_puzzle_to_string_with(&matrix, style)).collect();
+        assert_eq!(exported, ["12_43_1221__43_1", "12.43.1221..43.1", "1204301221004301"]);
+
+        for text in &exported {
+            assert_eq!(parse_puzzle(text).unwrap(), matrix);
+        }
+    }
+
+    #[test]
+    fn sizes_must_form_square_blocks() {
+        for size in [1, 4, 9, 16, 25] {
+            assert_eq!(assert_valid_size(size), Ok(()));
+        }
+        assert_eq!(assert_valid_size(0), Err(SizeError::Empty));
+        assert_eq!(assert_valid_size(6), Err(SizeError::NotSquare(6)));
+        assert_eq!(assert_valid_size(10), Err(SizeError::NotSquare(10)));
+
+        let mut ragged = puzzle();
+        ragged[2].push(0);
+        assert_eq!(assert_valid_board(&ragged), Err(SizeError::RowLength {row: 2, len: 10}));
+        assert_eq!(assert_valid_board(&puzzle()), Ok(()));
+    }
+
+    #[test]
+    fn backtracking_and_sat_enumerate_the_same_solutions() {
+        let ambiguous = parse_puzzle("1000000000000000").unwrap();
+
+        let mut backtracking = backtracking_all_solutions(&ambiguous, 1000);
+        let mut sat = collect_solutions_with(&ambiguous, &ConstraintSet::default(), 1000);
+        backtracking.sort();
+        sat.sort();
+
+        assert_eq!(backtracking.len(), count_solutions(&ambiguous, 1000));
+        assert_eq!(backtracking, sat);
+        assert_eq!(backtracking_all_solutions(&ambiguous, 5).len(), 5);
+    }
+
+    #[test]
+    fn origins_follow_entry_and_solving() {
+        let puzzle = parse_puzzle("1204301221004301").unwrap();
+        let mut origins = given_origins(&puzzle);
+        assert_eq!((origins[0][0], origins[0][2]), (CellOrigin::Given, CellOrigin::Empty));
+
+        let mut entered = puzzle.clone();
+        entered[0][2] = 3;
+        update_origins(&mut origins, &puzzle, &entered, CellOrigin::Player);
+
+        let mut solved = entered.clone();
+        assert!(solve_sat(&mut solved));
+        update_origins(&mut origins, &entered, &solved, CellOrigin::Solver);
+
+        assert_eq!(origins[0][0], CellOrigin::Given);
+        assert_eq!(origins[0][2], CellOrigin::Player);
+        assert_eq!(origins[1][1], CellOrigin::Solver);
+        assert_eq!(origins.iter().flatten().filter(|&&origin| origin == CellOrigin::Solver).count(), empty_count(&puzzle) - 1);
+
+        let mut cleared = solved.clone();
+        cleared[0][0] = 0;
+        update_origins(&mut origins, &solved, &cleared, CellOrigin::Player);
+        assert_eq!(origins[0][0], CellOrigin::Empty);
+    }
+
+    #[test]
+    fn pasted_rows_are_parsed_and_sized() {
+        let pasted = "1, 2, 0, 4\n3 . 1 2\n\n2,1,_,0\n4 3 0 1\n";
+        assert_eq!(parse_grid_rows(pasted, 4), parse_puzzle("1204301221004301"));
+
+        let big = (0..16).map(|row| if row == 0 {"16 ".repeat(16)} else {"0 ".repeat(16)}).collect::<Vec<_>>().join("\n");
+        assert_eq!(parse_grid_rows(&big, 16).unwrap()[0], [16; 16]);
+
+        assert_eq!(parse_grid_rows("1 2 0 4\n3 0 1\n2 1 0 0\n4 3 0 1", 4), Err(ParseError::RowLength {row: 1, len: 3}));
+        assert_eq!(parse_grid_rows("1 2 0 4\n3 0 1 2", 4), Err(ParseError::WrongSize {expected: 4, found: 2}));
+        assert_eq!(parse_grid_rows("1 2 0 5\n3 0 1 2\n2 1 0 0\n4 3 0 1", 4), Err(ParseError::ValueOutOfRange(5)));
+        assert_eq!(parse_grid_rows("1 2 0 x\n3 0 1 2\n2 1 0 0\n4 3 0 1", 4), Err(ParseError::InvalidNumber("x".to_string())));
+    }
+
+    #[test]
+    fn clue_and_empty_counts_cover_the_board() {
+        let empty = vec![vec![0; 9]; 9];
+        let mut full = puzzle();
+        assert!(solve_sat(&mut full));
+
+        assert_eq!((clue_count(&empty), empty_count(&empty)), (0, 81));
+        assert_eq!((clue_count(&full), empty_count(&full)), (81, 0));
+        assert_eq!((clue_count(&puzzle()), empty_count(&puzzle())), (30, 51));
+    }
+
+    #[test]
+    fn encoding_comparison_reports_every_encoding() {
+        let results = encoding_comparison(&puzzle());
+        let mut expected = puzzle();
+        assert!(solve_sat(&mut expected));
+
+        assert_eq!(results.iter().map(|result| result.encoding).collect::<Vec<_>>(), Encoding::ALL);
+        for result in &results {
+            assert_eq!(result.solution.as_ref(), Some(&expected), "{}", result.encoding);
+            assert!(result.seconds.is_finite() && result.variables >= 729 && result.clauses > 0);
+        }
+
+        let unsolvable = encoding_comparison(&parse_puzzle("1100000000000000").unwrap());
+        assert!(unsolvable.iter().all(|result| result.solution.is_none() && result.seconds.is_infinite()));
+    }
+
+    #[test]
+    fn the_core_holds_both_inconsistent_entries() {
+        // Two solutions, one with a 1 at (0, 0) and the other with a 2 at (2, 1): each entry fits one of them
+        let board = parse_puzzle("0034341200434321").unwrap();
+        assert!(find_unsatisfiable_core(&board).is_empty());
+
+        let mut entered = board.clone();
+        entered[0][0] = 1;
+        entered[2][1] = 2;
+        assert!(clue_conflicts(&entered, &ConstraintSet::default()).is_empty());
+
+        let core = find_unsatisfiable_core(&entered);
+        assert!(core.contains(&(0, 0)) && core.contains(&(2, 1)));
+
+        // Minimal: the core's cells alone are unsolvable, but not without any one of them
+        let mut core_only = vec![vec![0; 4]; 4];
+        for &(row, col) in &core {
+            core_only[row][col] = entered[row][col];
+        }
+        assert!(!solve_sat(&mut core_only.clone()));
+
+        for &(row, col) in &core {
+            let mut without = core_only.clone();
+            without[row][col] = 0;
+            assert!(solve_sat(&mut without), "({}, {})", row, col);
+        }
+    }
+
+    #[test]
+    fn a_jigsaw_puzzle_has_its_unique_solution() {
+        let constraints = ConstraintSet {regions: Some(Regions::from_map("AAAB CABB CCDB CDDD").unwrap()), ..Default::default()};
+        let matrix = parse_puzzle("0000000000020301").unwrap();
+        let solution = parse_puzzle("1234241331424321").unwrap();
+
+        assert_eq!(count_solutions_with(&matrix, &constraints, 10), 1);
+
+        let mut solved = matrix.clone();
+        assert!(solve_sat_with(&mut solved, &constraints));
+        assert_eq!(solved, solution);
+        assert!(is_complete_solution_with(&solution, &constraints));
+        assert!(!is_complete_solution(&solution)); // The top-left box holds two 2s
+    }
+
+    #[test]
+    fn value_and_index_conventions_convert_both_ways() {
+        assert_eq!(value_to_index(1), 0);
+        assert_eq!(index_to_value(0), 1);
+        assert_eq!(value_to_index(9), 8);
+        assert_eq!(index_to_value(8), 9);
+        assert_eq!(value_to_index(25), 24);
+        assert_eq!(index_to_value(24), 25);
+
+        assert!((1..=25).all(|value| index_to_value(value_to_index(value)) == value));
+    }
+
+    #[test]
+    fn completable_partials_and_dead_ends() {
+        let mut partial = parse_puzzle("0034341200434321").unwrap();
+        partial[0][0] = 1;
+        assert!(is_completable(&partial));
+        assert!(is_completable(&vec![vec![0; 9]; 9]));
+
+        // No rule is broken yet, but the 2 at (2, 1) belongs to the other solution
+        partial[2][1] = 2;
+        assert!(clue_conflicts(&partial, &ConstraintSet::default()).is_empty());
+        assert!(!is_completable(&partial));
+
+        let mut conflicting = puzzle();
+        conflicting[0][2] = 5;
+        assert!(!is_completable(&conflicting));
+        assert!(is_completable(&puzzle()));
+    }
+
+    #[test]
+    fn bitmask_bits_match_the_legal_values() {
+        let matrix = puzzle();
+        let masks = candidate_bitmasks(&matrix);
+
+        for row in 0..9 {
+            for col in 0..9 {
+                for k in 0..9 {
+                    let legal = matrix[row][col] == 0 && is_value_valid(&matrix, index_to_value(k), (row, col));
+                    assert_eq!(masks[row][col] & 1 << k != 0, legal, "({}, {}) value {}", row, col, k + 1);
                 }
             }
         }
+
+        assert_eq!(candidate_bitmasks(&vec![vec![0; 25]; 25])[0][0], (1 << 25) - 1);
     }
 
-    // 5) Pre-filled cells clauses
-    for r in 0..size {
-        for c in 0..size {
-            let val = matrix[r][c];
-            if val != 0 {
-                let n = (val - 1) as usize;
-                formula.add_clause(&[lit_from_indx(r, c, n, size)]); // unit clause
-            }
+    #[test]
+    fn arrows_move_the_cursor_within_the_board() {
+        let moves = |start, directions: &[Direction]| directions.iter().fold(start, |pos, &direction| move_cell(9, pos, direction));
+
+        assert_eq!(moves((4, 4), &[Direction::Up, Direction::Left, Direction::Left]), (3, 2));
+        assert_eq!(moves((4, 4), &[Direction::Down, Direction::Right]), (5, 5));
+
+        // Edges stop the cursor instead of wrapping
+        assert_eq!(moves((0, 0), &[Direction::Up, Direction::Left]), (0, 0));
+        assert_eq!(moves((8, 8), &[Direction::Down, Direction::Right]), (8, 8));
+        assert_eq!(moves((0, 8), &[Direction::Right, Direction::Down]), (1, 8));
+    }
+
+    #[test]
+    fn pretty_print_draws_the_blocks() {
+        let matrix = parse_puzzle("1204301221004301").unwrap();
+
+        assert_eq!(pretty_print(&matrix), "\
++-----+-----+
+| 1 2 | . 4 |
+| 3 . | 1 2 |
++-----+-----+
+| 2 1 | . . |
+| 4 3 | . 1 |
++-----+-----+
+");
+    }
+
+    #[test]
+    fn digit_counts_reach_size_on_a_solved_board() {
+        let mut solved = puzzle();
+        assert!(solve_sat(&mut solved));
+
+        assert_eq!(digit_counts(&solved), vec![9; 9]);
+        assert_eq!(digit_counts(&vec![vec![0; 9]; 9]), vec![0; 9]);
+        assert_eq!(digit_counts(&parse_puzzle("1204301221004301").unwrap()), [4, 3, 2, 2]);
+        assert_eq!(digit_counts(&puzzle()).iter().sum::<usize>(), clue_count(&puzzle()));
+    }
+
+    #[test]
+    fn the_constraint_graph_has_a_node_per_cell_and_an_edge_per_peer_pair() {
+        // Every cell of a 4x4 board has 7 peers (3 in its row, 3 in its column, 1 more in its block)
+        let dot = to_dot(&parse_puzzle("1204301221004301").unwrap());
+
+        assert_eq!(dot.lines().filter(|line| line.contains("[label=")).count(), 16);
+        assert_eq!(dot.lines().filter(|line| line.contains(" -- ")).count(), 16 * 7 / 2);
+        assert!(dot.contains("r0c2 [label=\".\"") && dot.contains("r0c0 [label=\"1\""));
+
+        assert_eq!(to_dot(&puzzle()).lines().filter(|line| line.contains(" -- ")).count(), 81 * 20 / 2);
+    }
+
+    #[test]
+    fn solve_times_use_a_unit_fitting_their_magnitude() {
+        assert_eq!(format_seconds(12.), "12.000 s");
+        assert_eq!(format_seconds(1.25), "1.250 s");
+        assert_eq!(format_seconds(0.00342), "3.42 ms");
+        assert_eq!(format_seconds(0.001), "1.00 ms");
+        assert_eq!(format_seconds(0.0000875), "87.5 \u{B5}s");
+        assert_eq!(format_seconds(0.), "0.0 \u{B5}s");
+        // Unsolvable puzzles report an infinite time
+        assert_eq!(format_seconds(f64::INFINITY), "inf s");
+    }
+
+    #[test]
+    fn only_fourth_powers_give_a_board_size() {
+        assert_eq!(infer_size(81), Some(3));
+        assert_eq!(infer_size(1), Some(1));
+        assert_eq!(infer_size(16), Some(2));
+        assert_eq!(infer_size(256), Some(4));
+
+        for cell_count in [0, 2, 9, 80, 82, 100, 255] {
+            assert_eq!(infer_size(cell_count), None, "{cell_count} cells");
         }
     }
 
-    formula
-}
\ No newline at end of file
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Solver returned an invalid solution")]
+    fn a_tampered_model_trips_the_solution_check() {
+        let matrix = parse_puzzle("1204301221004301").unwrap();
+
+        let mut solver = Solver::new();
+        solver.add_formula(&sudoku_to_sat(&matrix));
+        assert!(solver.solve().unwrap());
+
+        // Clearing the (0, 0) = 1 literal leaves that cell without a value
+        let mut model = solver.model().unwrap();
+        for lit in model.iter_mut().filter(|lit| **lit == lit_from_indx(0, 0, 0, 4)) {
+            *lit = !*lit;
+        }
+
+        let mut decoded = matrix.clone();
+        board_from_model(&model, 4).write_to(&mut decoded);
+        debug_assert_solution(&decoded, &ConstraintSet::default());
+    }
+}
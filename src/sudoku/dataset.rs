@@ -0,0 +1,229 @@
+use std::{fmt, fs, io, path::Path};
+use rayon::prelude::*;
+
+use super::{Algorithm, ClueEncoding, ConstraintSet, DEFAULT_FALLBACK_STEPS, ParseError, SolvedBy, check_against_solution, count_solutions, is_matrix_valid, parse_puzzle, solve, solve_reporting_time};
+
+/*
+    One row of a benchmark CSV (`puzzle,solution,difficulty`, as distributed by Sudoku Exchange and similar suites).
+    Extra columns are ignored and the difficulty label is kept verbatim, since every suite uses its own scale.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct PuzzleRecord {
+    pub puzzle: Vec<Vec<i8>>,
+    pub solution: Vec<Vec<i8>>,
+    pub difficulty: String,
+}
+
+impl PuzzleRecord {
+    // Solves a copy of the puzzle and compares it with the reference solution.
+    pub fn verify(&self, algorithm: Algorithm) -> bool {
+        let mut matrix = self.puzzle.clone();
+        solve(&mut matrix, algorithm) && matrix == self.solution
+    }
+}
+
+pub fn load_csv(path: &Path) -> Result<Vec<PuzzleRecord>, CsvError> {
+    parse_csv(&fs::read_to_string(path).map_err(CsvError::Io)?)
+}
+
+// Blank lines are skipped, and so is a leading header row (e.g. "puzzle,solution,difficulty").
+pub fn parse_csv(text: &str) -> Result<Vec<PuzzleRecord>, CsvError> {
+
+    let mut records = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {continue}
+
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() < 2 {
+            return Err(CsvError::MissingField(i + 1));
+        }
+
+        let puzzle = match parse_puzzle(fields[0]) {
+            Ok(puzzle) => puzzle,
+            Err(_) if records.is_empty() && fields[0].chars().all(char::is_alphabetic) => continue,
+            Err(err) => return Err(CsvError::InvalidGrid(i + 1, err)),
+        };
+        let solution = parse_puzzle(fields[1]).map_err(|err| CsvError::InvalidGrid(i + 1, err))?;
+
+        if solution.len() != puzzle.len() {
+            return Err(CsvError::SizeMismatch(i + 1));
+        }
+
+        records.push(PuzzleRecord {
+            puzzle,
+            solution,
+            difficulty: fields.get(2).unwrap_or(&"").to_string(),
+        });
+    }
+
+    Ok(records)
+}
+
+// How a solver's output compares with the reference solution shipped with its puzzle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReferenceCheck {
+    Matches,
+    OtherSolution,                 // Valid but different: the puzzle has several solutions, the reference is only one of them
+    Mismatch(Vec<(usize, usize)>), // Valid but different on a unique puzzle, so the reference itself is suspect
+    Invalid(Vec<(usize, usize)>),  // Blank cells, broken rules or changed clues in the output
+}
+
+/*
+    A reference is a single solution: output that differs from it is only an error if it breaks a rule
+    or the puzzle has no other solution. Only that last case needs solving again, to count solutions.
+*/
+pub fn compare_with_reference(puzzle: &[Vec<i8>], output: &[Vec<i8>], reference: &[Vec<i8>]) -> ReferenceCheck {
+
+    if output == reference {return ReferenceCheck::Matches}
+
+    let mut invalid = is_matrix_valid(output);
+    invalid.extend(check_against_solution(puzzle, output));
+    invalid.sort_unstable();
+    invalid.dedup();
+
+    if !invalid.is_empty() {
+        ReferenceCheck::Invalid(invalid)
+    } else if count_solutions(puzzle, 2) > 1 {
+        ReferenceCheck::OtherSolution
+    } else {
+        ReferenceCheck::Mismatch(check_against_solution(output, reference))
+    }
+}
+
+// Indices of the records whose solver output differs from the reference solution.
+pub fn find_mismatches(records: &[PuzzleRecord], algorithm: Algorithm) -> Vec<usize> {
+    records.iter()
+        .enumerate()
+        .filter(|(_, record)| !record.verify(algorithm))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+// Outcome of one puzzle in a batch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchEntry {
+    pub solution: Option<Vec<Vec<i8>>>,
+    pub seconds: f64, // Time of this solve alone (infinite when unsolvable)
+    pub solved_by: Option<SolvedBy>,
+}
+
+// Entries are in input order, whether or not the batch ran in parallel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchReport {
+    pub entries: Vec<BatchEntry>,
+}
+
+impl BatchReport {
+    pub fn solved(&self) -> usize {
+        self.entries.iter().filter(|entry| entry.solution.is_some()).count()
+    }
+
+    // Sum of the individual solve times (not wall-clock time, which is shorter in parallel).
+    pub fn total_seconds(&self) -> f64 {
+        self.entries.iter().filter(|entry| entry.seconds.is_finite()).map(|entry| entry.seconds).sum()
+    }
+}
+
+/*
+    Solves every puzzle independently. With `parallel`, puzzles are spread over rayon's thread pool;
+    each one is still timed on its own, and the results are collected back in input order.
+*/
+pub fn solve_batch(puzzles: &[Vec<Vec<i8>>], algorithm: Algorithm, parallel: bool) -> BatchReport {
+
+    let solve_one = |puzzle: &Vec<Vec<i8>>| {
+        let mut matrix = puzzle.clone();
+        let (seconds, solved_by) = solve_reporting_time(&mut matrix, algorithm, &ConstraintSet::default(), DEFAULT_FALLBACK_STEPS, ClueEncoding::default());
+
+        BatchEntry {solution: solved_by.map(|_| matrix), seconds, solved_by}
+    };
+
+    let entries = if parallel {
+        puzzles.par_iter().map(solve_one).collect()
+    } else {
+        puzzles.iter().map(solve_one).collect()
+    };
+
+    BatchReport {entries}
+}
+
+#[derive(Debug)]
+pub enum CsvError {
+    Io(io::Error),
+    MissingField(usize),            // Line number (1-based)
+    InvalidGrid(usize, ParseError), // Line number (1-based) and what went wrong
+    SizeMismatch(usize),            // Puzzle and solution have different sizes
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::Io(err) => write!(f, "could not read CSV: {}", err),
+            CsvError::MissingField(line) => write!(f, "line {}: expected at least puzzle and solution", line),
+            CsvError::InvalidGrid(line, err) => write!(f, "line {}: {}", line, err),
+            CsvError::SizeMismatch(line) => write!(f, "line {}: puzzle and solution sizes differ", line),
+        }
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = "\
+puzzle,solution,difficulty
+1204301221004301,1234341221434321,easy
+
+0034341212434321,1234341221434321,wrong
+";
+
+    #[test]
+    fn csv_rows_with_a_wrong_solution_are_reported() {
+        let records = parse_csv(FIXTURE).unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].difficulty, "easy");
+        assert_eq!(find_mismatches(&records, Algorithm::Sat), [1]);
+        assert!(matches!(parse_csv("1204301221004301"), Err(CsvError::MissingField(1))));
+        assert!(matches!(parse_csv("1204301221004301,123434122143432"), Err(CsvError::InvalidGrid(1, _))));
+    }
+
+    #[test]
+    fn parallel_batches_match_sequential_ones() {
+        let puzzles: Vec<Vec<Vec<i8>>> = ["1204301221004301", "0034341200434321", "1100000000000000", "1234341221434321"]
+            .into_iter()
+            .map(|text| parse_puzzle(text).unwrap())
+            .collect();
+
+        let sequential = solve_batch(&puzzles, Algorithm::Sat, false);
+        let parallel = solve_batch(&puzzles, Algorithm::Sat, true);
+
+        let outcome = |report: &BatchReport| report.entries.iter().map(|entry| (entry.solution.clone(), entry.solved_by)).collect::<Vec<_>>();
+        assert_eq!(outcome(&parallel), outcome(&sequential));
+        assert_eq!(sequential.solved(), 3);
+        assert!(sequential.entries[2].seconds.is_infinite());
+    }
+
+    #[test]
+    fn outputs_are_compared_with_the_reference() {
+        let solution = parse_puzzle("1234341221434321").unwrap();
+        let other = parse_puzzle("2134341212434321").unwrap(); // The other completion of the ambiguous board
+
+        let unique = parse_puzzle("1204301221004301").unwrap();
+        assert_eq!(compare_with_reference(&unique, &solution, &solution), ReferenceCheck::Matches);
+        // A wrong reference on a unique puzzle: the solver is right, the reference is suspect
+        assert_eq!(compare_with_reference(&unique, &solution, &other), ReferenceCheck::Mismatch(vec![(0, 0), (0, 1), (2, 0), (2, 1)]));
+
+        let ambiguous = parse_puzzle("0034341200434321").unwrap();
+        assert_eq!(compare_with_reference(&ambiguous, &other, &solution), ReferenceCheck::OtherSolution);
+
+        let mut broken = solution.clone();
+        broken[3][3] = 2;
+        assert!(matches!(compare_with_reference(&unique, &broken, &solution), ReferenceCheck::Invalid(cells) if cells.contains(&(3, 3))));
+        // A valid grid, but not over the puzzle's clues
+        assert_eq!(compare_with_reference(&unique, &other, &solution), ReferenceCheck::Invalid(vec![(0, 0), (0, 1), (2, 0), (2, 1)]));
+    }
+}
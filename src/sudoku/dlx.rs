@@ -0,0 +1,233 @@
+use super::{assert_valid_board, value_to_index};
+
+/*
+    Knuth's Algorithm X with Dancing Links, on the exact-cover form of Sudoku.
+    Every candidate (row, col, value) covers four constraints: the cell is filled, and the value
+    appears in its row, its column and its block. A solution picks candidates covering each constraint once.
+    Only the classic rules are encoded (no variants).
+*/
+pub fn solve_dlx(matrix: &mut [Vec<i8>]) -> bool {
+
+    if let Err(err) = assert_valid_board(matrix) {
+        println!("Cannot solve: {}", err);
+        return false;
+    }
+
+    let size = matrix.len();
+    let sub_size = size.isqrt();
+
+    let mut links = DancingLinks::new(4 * size * size);
+    let mut candidates: Vec<(usize, usize, i8)> = Vec::with_capacity(size * size * size);
+
+    for (row, values) in matrix.iter().enumerate() {
+        for (col, &given) in values.iter().enumerate() {
+            if given < 0 || given as usize > size {return false}
+
+            // A clue is the only candidate of its cell.
+            let cell_values = if given == 0 {1..=size as i8} else {given..=given};
+
+            for value in cell_values {
+                let n = value_to_index(value);
+                let block = (row / sub_size) * sub_size + col / sub_size;
+
+                links.add_row(&[
+                    row * size + col,
+                    size * size + row * size + n,
+                    2 * size * size + col * size + n,
+                    3 * size * size + block * size + n,
+                ]);
+                candidates.push((row, col, value));
+            }
+        }
+    }
+
+    let mut chosen: Vec<usize> = Vec::new();
+    if !links.search(&mut chosen) {
+        println!("No solution found.");
+        return false;
+    }
+
+    for id in chosen {
+        let (row, col, value) = candidates[id];
+        matrix[row][col] = value;
+    }
+
+    true
+}
+
+/*
+    Toroidal doubly linked lists stored in vectors. Node 0 is the root, nodes 1..=columns the column headers,
+    the rest are the 1s of the matrix, each knowing its column header and the id of its row.
+*/
+struct DancingLinks {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    header: Vec<usize>,
+    row_id: Vec<usize>,
+    sizes: Vec<usize>, // Nodes left in each column, indexed by header
+    rows: usize,
+}
+
+impl DancingLinks {
+    fn new(columns: usize) -> Self {
+        let nodes = columns + 1;
+
+        Self {
+            left: (0..nodes).map(|i| if i == 0 {columns} else {i - 1}).collect(),
+            right: (0..nodes).map(|i| if i == columns {0} else {i + 1}).collect(),
+            up: (0..nodes).collect(),
+            down: (0..nodes).collect(),
+            header: (0..nodes).collect(),
+            row_id: vec![usize::MAX; nodes],
+            sizes: vec![0; nodes],
+            rows: 0,
+        }
+    }
+
+    // Appends a row with 1s in the given (0-based) columns.
+    fn add_row(&mut self, columns: &[usize]) {
+        let mut first: Option<usize> = None;
+
+        for &column in columns {
+            let header = column + 1;
+            let node = self.left.len();
+
+            // Vertically: at the bottom of the column
+            self.up.push(self.up[header]);
+            self.down.push(header);
+            let above = self.up[header];
+            self.down[above] = node;
+            self.up[header] = node;
+
+            // Horizontally: at the end of the row, just before its first node
+            match first {
+                None => {
+                    self.left.push(node);
+                    self.right.push(node);
+                    first = Some(node);
+                }
+                Some(first) => {
+                    let last = self.left[first];
+                    self.left.push(last);
+                    self.right.push(first);
+                    self.right[last] = node;
+                    self.left[first] = node;
+                }
+            }
+
+            self.header.push(header);
+            self.row_id.push(self.rows);
+            self.sizes[header] += 1;
+        }
+
+        self.rows += 1;
+    }
+
+    fn cover(&mut self, header: usize) {
+        let (left, right) = (self.left[header], self.right[header]);
+        self.right[left] = right;
+        self.left[right] = left;
+
+        let mut i = self.down[header];
+        while i != header {
+            let mut j = self.right[i];
+            while j != i {
+                let (up, down) = (self.up[j], self.down[j]);
+                self.down[up] = down;
+                self.up[down] = up;
+                self.sizes[self.header[j]] -= 1;
+                j = self.right[j];
+            }
+            i = self.down[i];
+        }
+    }
+
+    // Exact reverse of cover, undoing the unlinks in the opposite order.
+    fn uncover(&mut self, header: usize) {
+        let mut i = self.up[header];
+        while i != header {
+            let mut j = self.left[i];
+            while j != i {
+                self.sizes[self.header[j]] += 1;
+                let (up, down) = (self.up[j], self.down[j]);
+                self.down[up] = j;
+                self.up[down] = j;
+                j = self.left[j];
+            }
+            i = self.up[i];
+        }
+
+        let (left, right) = (self.left[header], self.right[header]);
+        self.right[left] = header;
+        self.left[right] = header;
+    }
+
+    // Algorithm X, always branching on the column with the fewest rows. `chosen` ends up with the solution rows.
+    fn search(&mut self, chosen: &mut Vec<usize>) -> bool {
+
+        if self.right[0] == 0 {return true}
+
+        let mut column = self.right[0];
+        let mut c = self.right[column];
+        while c != 0 {
+            if self.sizes[c] < self.sizes[column] {column = c}
+            c = self.right[c];
+        }
+
+        if self.sizes[column] == 0 {return false}
+
+        self.cover(column);
+
+        let mut r = self.down[column];
+        while r != column {
+            chosen.push(self.row_id[r]);
+
+            let mut j = self.right[r];
+            while j != r {
+                self.cover(self.header[j]);
+                j = self.right[j];
+            }
+
+            if self.search(chosen) {return true}
+
+            let mut j = self.left[r];
+            while j != r {
+                self.uncover(self.header[j]);
+                j = self.left[j];
+            }
+
+            chosen.pop();
+            r = self.down[r];
+        }
+
+        self.uncover(column);
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sudoku::{is_complete_solution, parse_puzzle, solve_sat};
+
+    #[test]
+    fn dlx_agrees_with_sat() {
+        let puzzles = [
+            "530070000600195000098000060800060003400803001700020006060000280000419005000080079",
+            "000000000000003085001020000000507000004000100090000000500000073002010000000040009",
+            "1204301221004301",
+        ];
+
+        for text in puzzles {
+            let (mut dlx, mut sat) = (parse_puzzle(text).unwrap(), parse_puzzle(text).unwrap());
+
+            assert!(solve_dlx(&mut dlx) && solve_sat(&mut sat), "{}", text);
+            assert!(is_complete_solution(&dlx));
+            assert_eq!(dlx, sat); // Unique puzzles: both must find the one solution
+        }
+
+        assert!(!solve_dlx(&mut parse_puzzle("1100000000000000").unwrap()));
+    }
+}
@@ -0,0 +1,72 @@
+use std::{fmt, fs, io, path::Path};
+
+use super::{ParseError, parse_puzzle};
+
+pub fn load_ss(path: &Path) -> Result<Vec<Vec<i8>>, SsError> {
+    parse_ss(&fs::read_to_string(path).map_err(SsError::Io)?)
+}
+
+/*
+    Simple Sudoku (.ss) grids: one line per row, '.' for blanks, '|' between stacks
+    and separator lines ("---+---+---", "-----------", "*-----------*") between bands.
+    Separator lines have no cell in them, so they are skipped; pipes are dropped from the others.
+*/
+pub fn parse_ss(text: &str) -> Result<Vec<Vec<i8>>, SsError> {
+
+    let cells: String = text.lines()
+        .filter(|line| line.chars().any(|ch| ch == '.' || ch.is_ascii_alphanumeric()))
+        .flat_map(|line| line.chars().filter(|&ch| ch != '|' && ch != '*'))
+        .collect();
+
+    parse_puzzle(&cells).map_err(SsError::InvalidGrid)
+}
+
+#[derive(Debug)]
+pub enum SsError {
+    Io(io::Error),
+    InvalidGrid(ParseError),
+}
+
+impl fmt::Display for SsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SsError::Io(err) => write!(f, "could not read .ss file: {}", err),
+            SsError::InvalidGrid(err) => write!(f, "invalid .ss grid: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for SsError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = "\
+*-----------*
+|53.|.7.|...|
+|6..|195|...|
+|.98|...|.6.|
+|---+---+---|
+|8..|.6.|..3|
+|4..|8.3|..1|
+|7..|.2.|..6|
+|---+---+---|
+|.6.|...|28.|
+|...|419|..5|
+|...|.8.|.79|
+*-----------*
+";
+
+    #[test]
+    fn ss_files_load_into_the_grid() {
+        let path = std::env::temp_dir().join(format!("sudoku_fixture_{}.ss", std::process::id()));
+        fs::write(&path, FIXTURE).unwrap();
+
+        let matrix = load_ss(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(matrix.unwrap(), parse_puzzle("530070000600195000098000060800060003400803001700020006060000280000419005000080079").unwrap());
+        assert!(matches!(parse_ss("|53.|.7.|"), Err(SsError::InvalidGrid(_))));
+    }
+}
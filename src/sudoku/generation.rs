@@ -0,0 +1,503 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use chrono::{Datelike, NaiveDate};
+use rand::{Rng, SeedableRng, rng, rngs::StdRng, seq::SliceRandom};
+
+use super::{ConstraintSet, Technique, assert_valid_board, clue_count, count_solutions, count_solutions_with, generate_random_matrix_with, hardest_technique, rate_difficulty, solve_sat};
+
+// Attempts made by generate_for_score (and duplicate puzzles tolerated by minimal_puzzles_from_solution) before giving up.
+pub const MAX_GENERATION_ATTEMPTS: usize = 500;
+
+// A random complete grid: a few random clues, then completed by the SAT solver.
+pub fn random_solution(size: usize) -> Vec<Vec<i8>> {
+    random_solution_with(size, &mut rng())
+}
+
+pub fn random_solution_with(size: usize, random: &mut impl Rng) -> Vec<Vec<i8>> {
+    loop {
+        let mut matrix = vec![vec![0i8; size]; size];
+        generate_random_matrix_with(&mut matrix, size, random);
+
+        if solve_sat(&mut matrix) {
+            return matrix;
+        }
+    }
+}
+
+/*
+    Reproducible puzzle with a unique solution: the same size and seed always give the same grid.
+    Clues of a seeded random solution are removed in seeded random order, as long as the solution stays unique.
+    Note: StdRng may change between rand releases, so seeds are only stable for a given build.
+*/
+pub fn generate_seeded(size: usize, seed: u64) -> Vec<Vec<i8>> {
+
+    let mut random = StdRng::seed_from_u64(seed);
+    let solution = random_solution_with(size, &mut random);
+
+    minimize_clues(&solution, &mut random)
+}
+
+/*
+    Removes clues in random order, each one only if the solution stays unique.
+    Every clue has been tried once at the end, so the result is minimal (no single clue can go).
+    Different orders lead to different minimal puzzles.
+*/
+pub fn minimize_clues(puzzle: &[Vec<i8>], random: &mut impl Rng) -> Vec<Vec<i8>> {
+
+    let size = puzzle.len();
+
+    let mut positions: Vec<usize> = (0..size * size).collect();
+    positions.shuffle(random);
+
+    remove_clues_in_order(puzzle, &positions, |_| true)
+}
+
+// Order in which puzzle_from_solution and the clue-count generator try to remove clues.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RemovalOrder {
+    ReadingOrder, // Row by row: always the same puzzle, biased towards keeping clues at the bottom
+    Shuffled(u64), // Seeded random order: one minimal puzzle per seed
+}
+
+impl RemovalOrder {
+    // Positions (row * size + col) of a board with `cells` cells, in the order their clues are tried.
+    pub fn positions(self, cells: usize) -> Vec<usize> {
+        let mut positions: Vec<usize> = (0..cells).collect();
+        if let RemovalOrder::Shuffled(seed) = self {
+            positions.shuffle(&mut StdRng::seed_from_u64(seed));
+        }
+        positions
+    }
+}
+
+// A minimal unique puzzle whose solution is `solution`.
+pub fn puzzle_from_solution(solution: &[Vec<i8>], order: RemovalOrder) -> Vec<Vec<i8>> {
+    remove_clues_in_order(solution, &order.positions(solution.len().pow(2)), |_| true)
+}
+
+/*
+    The opposite extreme of a minimal puzzle: as many clues as possible while still leaving something to solve.
+    A lone blank is always forced by its row, so this is `solution` with its first cell removed (size² - 1 clues);
+    the removal still goes through the uniqueness check like every other.
+*/
+pub fn maximal_incomplete(solution: &[Vec<i8>]) -> Vec<Vec<i8>> {
+    let cells = solution.len().pow(2);
+    let positions: Vec<usize> = (0..cells).collect();
+
+    remove_clues_in_order(solution, &positions, |puzzle| clue_count(puzzle) + 1 >= cells)
+}
+
+// Tries each position (row * size + col) once, keeping the blank only if the solution stays unique and `allowed` accepts the puzzle.
+fn remove_clues_in_order(puzzle: &[Vec<i8>], positions: &[usize], allowed: impl Fn(&[Vec<i8>]) -> bool) -> Vec<Vec<i8>> {
+    remove_clues_cancellable(puzzle, positions, allowed, &AtomicBool::new(false)).expect("Never cancelled")
+}
+
+// Same as remove_clues_in_order, giving up (None) as soon as `cancel` is set, checked before every uniqueness test.
+fn remove_clues_cancellable(puzzle: &[Vec<i8>], positions: &[usize], allowed: impl Fn(&[Vec<i8>]) -> bool, cancel: &AtomicBool) -> Option<Vec<Vec<i8>>> {
+
+    let size = puzzle.len();
+    let mut puzzle = puzzle.to_vec();
+
+    for &i in positions {
+        if cancel.load(Ordering::Relaxed) {return None}
+
+        let clue = std::mem::take(&mut puzzle[i / size][i % size]);
+
+        if !allowed(&puzzle) || count_solutions(&puzzle, 2) != 1 {
+            puzzle[i / size][i % size] = clue;
+        }
+    }
+
+    Some(puzzle)
+}
+
+/*
+    Up to `count` distinct minimal puzzles sharing `solution`, for building datasets.
+    Small grids have few minimal puzzles: after MAX_GENERATION_ATTEMPTS tries without reaching `count`, the ones found are returned.
+*/
+pub fn minimal_puzzles_from_solution(solution: &[Vec<i8>], count: usize, seed: u64) -> Vec<Vec<Vec<i8>>> {
+
+    let mut random = StdRng::seed_from_u64(seed);
+    let mut puzzles: Vec<Vec<Vec<i8>>> = Vec::with_capacity(count);
+    let mut misses = 0;
+
+    while puzzles.len() < count && misses < MAX_GENERATION_ATTEMPTS {
+        let puzzle = minimize_clues(solution, &mut random);
+
+        if puzzles.contains(&puzzle) {
+            misses += 1;
+        } else {
+            puzzles.push(puzzle);
+        }
+    }
+
+    puzzles
+}
+
+// Daily 9x9 challenge: everyone gets the same puzzle for a given date.
+pub fn puzzle_of_the_day(date: NaiveDate) -> Vec<Vec<i8>> {
+    generate_seeded(9, daily_seed(date))
+}
+
+pub fn daily_seed(date: NaiveDate) -> u64 {
+    date.num_days_from_ce() as u64
+}
+
+/*
+    Generates puzzles (random solution, then a random number of cells blanked out)
+    until one rates within target ± tolerance, see rate_difficulty.
+*/
+pub fn generate_for_score(target: f64, tolerance: f64, size: usize) -> Result<Vec<Vec<i8>>, GenerationError> {
+
+    let cells = size * size;
+    let mut closest: Option<f64> = None;
+
+    for _ in 0..MAX_GENERATION_ATTEMPTS {
+        let mut puzzle = random_solution(size);
+
+        let mut positions: Vec<usize> = (0..cells).collect();
+        positions.shuffle(&mut rng());
+
+        let blanks = rng().random_range(cells / 3..=cells);
+        for &i in &positions[..blanks] {
+            puzzle[i / size][i % size] = 0;
+        }
+
+        let Some(score) = rate_difficulty(&puzzle) else {continue};
+
+        if (score - target).abs() <= tolerance {
+            return Ok(puzzle);
+        }
+
+        if closest.is_none_or(|c| (score - target).abs() < (c - target).abs()) {
+            closest = Some(score);
+        }
+    }
+
+    Err(GenerationError::TargetNotReached {attempts: MAX_GENERATION_ATTEMPTS, closest})
+}
+
+/*
+    Puzzle for practicing `technique`: clues of a random solution are removed as long as the solution stays unique
+    and no harder technique becomes necessary, then the puzzle is kept if it actually needs `technique`.
+    The puzzle has a unique solution.
+*/
+pub fn generate_for_technique(technique: Technique, size: usize) -> Result<Vec<Vec<i8>>, GenerationError> {
+
+    let mut random = rng();
+
+    for _ in 0..MAX_GENERATION_ATTEMPTS {
+        let solution = random_solution_with(size, &mut random);
+
+        let mut positions: Vec<usize> = (0..size * size).collect();
+        positions.shuffle(&mut random);
+
+        let puzzle = remove_clues_in_order(&solution, &positions, |puzzle| {
+            hardest_technique(puzzle).is_some_and(|hardest| hardest <= technique)
+        });
+
+        if hardest_technique(&puzzle) == Some(technique) {
+            return Ok(puzzle);
+        }
+    }
+
+    Err(GenerationError::TechniqueNotRequired {technique, attempts: MAX_GENERATION_ATTEMPTS})
+}
+
+/*
+    Unique puzzle with `target` clues, or as close as one pass of clue removal in `order` gets:
+    the result tells when the target was missed and why instead of silently settling.
+*/
+pub fn generate_with_clues(size: usize, target: usize, order: RemovalOrder) -> GenerationResult {
+    generate_with_clues_cancellable(size, target, order, &AtomicBool::new(false)).expect("Never cancelled")
+}
+
+/*
+    Same as generate_with_clues, for background threads: None once `cancel` is set (by the UI, when the parameters
+    changed), so a stale run stops early instead of finishing a puzzle nobody waits for.
+*/
+pub fn generate_with_clues_cancellable(size: usize, target: usize, order: RemovalOrder, cancel: &AtomicBool) -> Option<GenerationResult> {
+
+    let cells = size * size;
+    let solution = random_solution(size);
+
+    let puzzle = remove_clues_cancellable(&solution, &order.positions(cells), |puzzle| clue_count(puzzle) >= target, cancel)?;
+    let clues = clue_count(&puzzle);
+
+    let missed = if target > cells {
+        Some(TargetMissed::TooManyClues {cells})
+    } else if clues > target {
+        Some(TargetMissed::UniquenessLost {clues})
+    } else {
+        None
+    };
+
+    Some(GenerationResult {puzzle, target, missed})
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenerationResult {
+    pub puzzle: Vec<Vec<i8>>,
+    pub target: usize, // Requested clue count
+    pub missed: Option<TargetMissed>,
+}
+
+impl GenerationResult {
+    pub fn target_met(&self) -> bool {
+        self.missed.is_none()
+    }
+}
+
+// Why generate_with_clues stopped short of its target; the puzzle is the closest one found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetMissed {
+    TooManyClues {cells: usize},  // More clues than cells: the puzzle is the full grid
+    UniquenessLost {clues: usize}, // Removing any further clue made the solution ambiguous
+}
+
+impl fmt::Display for TargetMissed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TargetMissed::TooManyClues {cells} => write!(f, "the board only has {} cells", cells),
+            TargetMissed::UniquenessLost {clues} => write!(f, "couldn't maintain uniqueness below {} clues", clues),
+        }
+    }
+}
+
+/*
+    Clues that can be removed one at a time while keeping the solution unique,
+    i.e. a non-empty result means the puzzle isn't minimal. Empty if the puzzle itself isn't unique.
+*/
+pub fn redundant_clues(matrix: &[Vec<i8>]) -> Vec<(usize, usize)> {
+    redundant_clues_with(matrix, &ConstraintSet::default())
+}
+
+// Same as redundant_clues, uniqueness being judged under `constraints` (variants, jigsaw regions...).
+pub fn redundant_clues_with(matrix: &[Vec<i8>], constraints: &ConstraintSet) -> Vec<(usize, usize)> {
+
+    if assert_valid_board(matrix).is_err() {return Vec::new()}
+    if count_solutions_with(matrix, constraints, 2) != 1 {return Vec::new()}
+
+    let size = matrix.len();
+    let mut puzzle = matrix.to_vec();
+    let mut redundant: Vec<(usize, usize)> = Vec::new();
+
+    for row in 0..size {
+        for col in 0..size {
+            let clue = puzzle[row][col];
+            if clue == 0 {continue}
+
+            puzzle[row][col] = 0;
+            if count_solutions_with(&puzzle, constraints, 2) == 1 {
+                redundant.push((row, col));
+            }
+            puzzle[row][col] = clue;
+        }
+    }
+
+    redundant
+}
+
+/*
+    How evenly the clues are spread, from 0 (no clues) to 1 (every row, column and block has as many).
+    For rows, columns and blocks, the clue counts' coefficient of variation (std dev / mean) is computed;
+    the score is 1 / (1 + their average), so clustered layouts score lower.
+*/
+pub fn clue_distribution_score(matrix: &[Vec<i8>]) -> f64 {
+
+    let clues = clue_count(matrix);
+    if clues == 0 {return 0.}
+
+    let size = matrix.len();
+    let sub_size = size.isqrt();
+
+    let mut rows = vec![0usize; size];
+    let mut cols = vec![0usize; size];
+    let mut blocks = vec![0usize; size];
+
+    for (row, values) in matrix.iter().enumerate() {
+        for (col, &value) in values.iter().enumerate() {
+            if value == 0 {continue}
+            rows[row] += 1;
+            cols[col] += 1;
+            blocks[(row / sub_size) * sub_size + col / sub_size] += 1;
+        }
+    }
+
+    let mean = clues as f64 / size as f64;
+    let variation = |counts: &[usize]| {
+        let variance = counts.iter().map(|&n| (n as f64 - mean).powi(2)).sum::<f64>() / size as f64;
+        variance.sqrt() / mean
+    };
+
+    1. / (1. + (variation(&rows) + variation(&cols) + variation(&blocks)) / 3.)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GenerationError {
+    TargetNotReached {attempts: usize, closest: Option<f64>},
+    TechniqueNotRequired {technique: Technique, attempts: usize},
+}
+
+impl fmt::Display for GenerationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenerationError::TargetNotReached {attempts, closest: Some(closest)} =>
+                write!(f, "no puzzle in the requested band after {} attempts (closest score {:.2})", attempts, closest),
+            GenerationError::TargetNotReached {attempts, closest: None} =>
+                write!(f, "no puzzle in the requested band after {} attempts", attempts),
+            GenerationError::TechniqueNotRequired {technique, attempts} =>
+                write!(f, "no puzzle requiring {} after {} attempts", technique, attempts),
+        }
+    }
+}
+
+impl std::error::Error for GenerationError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sudoku::{Variant, check_against_solution, empty_count, parse_puzzle, solve_with_steps};
+
+    #[test]
+    fn redundant_clues_judge_uniqueness_under_the_constraints() {
+        // Unique only with the diagonals, and still so without its top-left clue
+        let puzzle = parse_puzzle("3000000000030321").unwrap();
+        let mut constraints = ConstraintSet::default();
+        constraints.set_variant(Variant::Diagonal, true);
+
+        assert!(redundant_clues(&puzzle).is_empty());
+        assert!(redundant_clues_with(&puzzle, &constraints).contains(&(0, 0)));
+
+        let ragged = vec![vec![1, 0, 0, 0], vec![0; 3], vec![0; 4], vec![0; 4]];
+        assert!(redundant_clues(&ragged).is_empty());
+    }
+
+    #[test]
+    fn removal_order_seeds_give_different_puzzles() {
+        let solution = random_solution_with(9, &mut StdRng::seed_from_u64(7));
+
+        let first = puzzle_from_solution(&solution, RemovalOrder::Shuffled(1));
+        let second = puzzle_from_solution(&solution, RemovalOrder::Shuffled(2));
+
+        assert_ne!(first, second);
+        assert_eq!(puzzle_from_solution(&solution, RemovalOrder::Shuffled(1)), first);
+        assert!([&first, &second].iter().all(|puzzle| count_solutions(puzzle, 2) == 1));
+    }
+
+    #[test]
+    fn scored_puzzles_fall_within_the_band() {
+        for _ in 0..5 {
+            let puzzle = generate_for_score(1.5, 0.25, 4).unwrap();
+            let score = rate_difficulty(&puzzle).unwrap();
+
+            assert!((1.25..=1.75).contains(&score), "score {} outside the band", score);
+        }
+
+        // Guessing every placement still weighs less than 10
+        assert!(matches!(generate_for_score(10., 0.5, 4), Err(GenerationError::TargetNotReached {closest: Some(_), ..})));
+    }
+
+    #[test]
+    fn the_daily_puzzle_depends_only_on_the_date() {
+        let day = NaiveDate::from_ymd_opt(2024, 3, 14).unwrap();
+        let puzzle = puzzle_of_the_day(day);
+
+        assert_eq!(puzzle_of_the_day(day), puzzle);
+        assert_ne!(puzzle_of_the_day(day.succ_opt().unwrap()), puzzle);
+        assert_eq!(count_solutions(&puzzle, 2), 1);
+    }
+
+    #[test]
+    fn minimal_puzzles_are_unique_minimal_and_distinct() {
+        let solution = random_solution_with(9, &mut StdRng::seed_from_u64(3));
+        let puzzles = minimal_puzzles_from_solution(&solution, 3, 11);
+
+        assert_eq!(puzzles.len(), 3);
+        assert!(puzzles[0] != puzzles[1] && puzzles[1] != puzzles[2] && puzzles[0] != puzzles[2]);
+
+        for puzzle in &puzzles {
+            assert_eq!(count_solutions(puzzle, 2), 1);
+            assert!(redundant_clues(puzzle).is_empty());
+            assert!(check_against_solution(puzzle, &solution).is_empty());
+        }
+    }
+
+    #[test]
+    fn spread_clues_score_above_clustered_ones() {
+        let solution = random_solution_with(9, &mut StdRng::seed_from_u64(5));
+
+        // 27 clues each: every third cell, or the whole top band
+        let mut spread = solution.clone();
+        let mut clustered = solution.clone();
+        for i in 0..81 {
+            if i % 3 != 0 {spread[i / 9][i % 9] = 0}
+            if i >= 27 {clustered[i / 9][i % 9] = 0}
+        }
+
+        assert!(clue_distribution_score(&spread) > clue_distribution_score(&clustered));
+        assert!((clue_distribution_score(&solution) - 1.).abs() < 1e-9);
+        assert_eq!(clue_distribution_score(&vec![vec![0; 9]; 9]), 0.);
+    }
+
+    #[test]
+    fn hidden_single_practice_needs_hidden_singles() {
+        let puzzle = generate_for_technique(Technique::HiddenSingle, 9).unwrap();
+        let techniques: Vec<Technique> = solve_with_steps(&puzzle).unwrap().into_iter().map(|step| step.technique).collect();
+
+        // Naked singles always go first, so a hidden single means they ran out
+        assert!(techniques.contains(&Technique::HiddenSingle));
+        assert!(techniques.iter().all(|&technique| technique <= Technique::HiddenSingle));
+        assert_eq!(count_solutions(&puzzle, 2), 1);
+    }
+
+    #[test]
+    fn impossible_clue_targets_are_reported() {
+        // More clues than cells: the full grid is the closest
+        let too_many = generate_with_clues(4, 20, RemovalOrder::ReadingOrder);
+        assert_eq!(too_many.missed, Some(TargetMissed::TooManyClues {cells: 16}));
+        assert_eq!(clue_count(&too_many.puzzle), 16);
+
+        // No 9x9 puzzle with 5 clues has a unique solution
+        let too_few = generate_with_clues(9, 5, RemovalOrder::Shuffled(1));
+        assert!(matches!(too_few.missed, Some(TargetMissed::UniquenessLost {clues}) if clues > 5 && clues == clue_count(&too_few.puzzle)));
+        assert!(!too_few.target_met());
+        assert_eq!(count_solutions(&too_few.puzzle, 2), 1);
+
+        assert!(generate_with_clues(4, 14, RemovalOrder::ReadingOrder).target_met());
+    }
+
+    #[test]
+    fn generation_stops_once_cancelled() {
+        let cancel = AtomicBool::new(true);
+        assert!(generate_with_clues_cancellable(9, 30, RemovalOrder::ReadingOrder, &cancel).is_none());
+
+        cancel.store(false, Ordering::Relaxed);
+        assert!(generate_with_clues_cancellable(4, 6, RemovalOrder::ReadingOrder, &cancel).is_some());
+
+        // Cancelled mid-run, after five clues were tried
+        let solution = random_solution_with(9, &mut StdRng::seed_from_u64(1));
+        let tried = std::cell::Cell::new(0);
+        let result = remove_clues_cancellable(&solution, &RemovalOrder::ReadingOrder.positions(81), |_| {
+            tried.set(tried.get() + 1);
+            if tried.get() == 5 {cancel.store(true, Ordering::Relaxed)}
+            true
+        }, &cancel);
+
+        assert!(result.is_none());
+        assert_eq!(tried.get(), 5);
+    }
+
+    #[test]
+    fn the_anti_puzzle_misses_a_single_clue() {
+        for size in [4, 9] {
+            let solution = random_solution_with(size, &mut StdRng::seed_from_u64(size as u64));
+            let puzzle = maximal_incomplete(&solution);
+
+            assert_eq!(empty_count(&puzzle), 1);
+            assert_eq!(puzzle[0][0], 0);
+            assert_eq!(count_solutions(&puzzle, 2), 1);
+            assert!(check_against_solution(&puzzle, &solution).is_empty());
+        }
+    }
+}
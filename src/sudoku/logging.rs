@@ -0,0 +1,108 @@
+use std::{env, fmt, fs::{File, OpenOptions}, io::{self, Write}, path::Path, sync::atomic::{AtomicUsize, Ordering}};
+
+// Environment variable holding the log file path, e.g. SUDOKU_SOLVER_LOG=solver.log
+pub const SOLVER_LOG_ENV: &str = "SUDOKU_SOLVER_LOG";
+
+// Lines are written in chunks of about this size, always ending on a full line.
+const FLUSH_BYTES: usize = 64 * 1024;
+
+static NEXT_SOLVE: AtomicUsize = AtomicUsize::new(1);
+
+/*
+    Opt-in trace of solver decisions, for reporting pathological puzzles.
+    When disabled nothing is formatted or written, so solving speed is unaffected.
+    Every line starts with the solve's number, e.g. "[3] assign 0 2 4": solves logging to the same file
+    (one after the other, or side by side in solve_batch) append whole lines and can still be told apart.
+*/
+pub struct SolverLog {
+    file: Option<File>,
+    buffer: Vec<u8>,
+    solve: usize,
+}
+
+impl SolverLog {
+    pub fn disabled() -> Self {
+        Self {file: None, buffer: Vec::new(), solve: 0}
+    }
+
+    // A fresh log at `path`, replacing any previous content.
+    pub fn to_file(path: &Path) -> io::Result<Self> {
+        Ok(Self::writing_to(File::create(path)?))
+    }
+
+    // Enabled only if SUDOKU_SOLVER_LOG is set (and the file can be opened), appending to what earlier solves logged.
+    pub fn from_env() -> Self {
+        let Some(path) = env::var_os(SOLVER_LOG_ENV) else {return Self::disabled()};
+
+        Self::appending_to(Path::new(&path)).unwrap_or_else(|err| {
+            println!("Solver log disabled: {}", err);
+            Self::disabled()
+        })
+    }
+
+    // Adds this solve's lines after what `path` already holds.
+    pub fn appending_to(path: &Path) -> io::Result<Self> {
+        Ok(Self::writing_to(OpenOptions::new().create(true).append(true).open(path)?))
+    }
+
+    fn writing_to(file: File) -> Self {
+        Self {file: Some(file), buffer: Vec::with_capacity(FLUSH_BYTES), solve: NEXT_SOLVE.fetch_add(1, Ordering::Relaxed)}
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.file.is_some()
+    }
+
+    pub fn log(&mut self, args: fmt::Arguments<'_>) {
+        if self.file.is_none() {return}
+
+        let _ = writeln!(self.buffer, "[{}] {}", self.solve, args);
+        if self.buffer.len() >= FLUSH_BYTES {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some(file) = &mut self.file
+            && file.write_all(&self.buffer).is_err() {
+            self.file = None; // Stop logging rather than failing the solve
+        }
+        self.buffer.clear();
+    }
+}
+
+impl Drop for SolverLog {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sudoku::{ConstraintSet, empty_count, parse_puzzle, solve_backtracking_logged};
+
+    #[test]
+    fn solves_append_numbered_lines() {
+        let path = env::temp_dir().join(format!("sudoku_solver_log_{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let puzzle = parse_puzzle("1204301221004301").unwrap();
+
+        for _ in 0..2 {
+            let mut matrix = puzzle.clone();
+            assert!(solve_backtracking_logged(&mut matrix, &ConstraintSet::default(), &mut SolverLog::appending_to(&path).unwrap()));
+        }
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let solves: Vec<&str> = text.lines().filter(|line| line.ends_with("result solved")).map(|line| line.split(' ').next().unwrap()).collect();
+        assert_eq!(solves.len(), 2);
+        assert_ne!(solves[0], solves[1]);
+
+        // Every assignment that wasn't taken back fills one empty cell.
+        let count = |word: &str| text.lines().filter(|line| line.split(' ').nth(1) == Some(word)).count();
+        assert_eq!(count("assign") - count("backtrack"), 2 * empty_count(&puzzle));
+    }
+}
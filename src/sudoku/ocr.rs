@@ -0,0 +1,125 @@
+use std::{fmt, fs, io, path::{Path, PathBuf}};
+
+use super::{ParseError, is_matrix_valid};
+
+/*
+    Extension point for importing puzzles from photos/screenshots.
+    A backend only has to recognize digits: turning them into a grid and validating it is shared.
+*/
+pub trait GridOcr {
+    // Recognized cells, row by row (None for blanks).
+    fn read_cells(&self, image: &Path) -> Result<Vec<Option<u8>>, OcrError>;
+}
+
+pub fn import_from_image(ocr: &dyn GridOcr, image: &Path) -> Result<Vec<Vec<i8>>, OcrError> {
+    grid_from_ocr(&ocr.read_cells(image)?)
+}
+
+// Builds the grid and rejects shapes, values or given conflicts that point at a misread.
+pub fn grid_from_ocr(cells: &[Option<u8>]) -> Result<Vec<Vec<i8>>, OcrError> {
+
+    let size = cells.len().isqrt();
+    if cells.is_empty() || size * size != cells.len() || size.isqrt().pow(2) != size {
+        return Err(OcrError::InvalidGrid(ParseError::InvalidLength(cells.len())));
+    }
+
+    let mut matrix = vec![vec![0i8; size]; size];
+
+    for (i, cell) in cells.iter().enumerate() {
+        if let Some(digit) = *cell {
+            if digit == 0 || digit as usize > size {
+                return Err(OcrError::InvalidGrid(ParseError::ValueOutOfRange(digit as i8)));
+            }
+            matrix[i / size][i % size] = digit as i8;
+        }
+    }
+
+    let conflicts: Vec<(usize, usize)> = is_matrix_valid(&matrix)
+        .into_iter()
+        .filter(|&(r, c)| matrix[r][c] != 0)
+        .collect();
+
+    if !conflicts.is_empty() {
+        return Err(OcrError::Conflicts(conflicts));
+    }
+
+    Ok(matrix)
+}
+
+/*
+    "Manual OCR": reads a transcription stored next to the image (photo.png -> photo.png.txt),
+    in the same format accepted by parse_puzzle. Useful until a real recognizer is plugged in.
+*/
+pub struct SidecarOcr;
+
+impl SidecarOcr {
+    pub fn sidecar_path(image: &Path) -> PathBuf {
+        let mut path = image.as_os_str().to_owned();
+        path.push(".txt");
+        PathBuf::from(path)
+    }
+}
+
+impl GridOcr for SidecarOcr {
+    fn read_cells(&self, image: &Path) -> Result<Vec<Option<u8>>, OcrError> {
+        let text = fs::read_to_string(Self::sidecar_path(image)).map_err(OcrError::Io)?;
+
+        text.chars()
+            .filter(|ch| !ch.is_whitespace())
+            .map(|ch| match ch {
+                '.' | '0' => Ok(None),
+                _ => ch.to_digit(36).map(|d| Some(d as u8)).ok_or(OcrError::InvalidGrid(ParseError::InvalidChar(ch))),
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+pub enum OcrError {
+    Io(io::Error),
+    InvalidGrid(ParseError),
+    Conflicts(Vec<(usize, usize)>), // Recognized givens that break the rules, likely misread
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OcrError::Io(err) => write!(f, "could not read image data: {}", err),
+            OcrError::InvalidGrid(err) => write!(f, "invalid grid: {}", err),
+            OcrError::Conflicts(cells) => write!(f, "{} recognized cells conflict with each other", cells.len()),
+        }
+    }
+}
+
+impl std::error::Error for OcrError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sudoku::parse_puzzle;
+
+    // Always "recognizes" the same cells, whatever the image.
+    struct MockOcr(Vec<Option<u8>>);
+
+    impl GridOcr for MockOcr {
+        fn read_cells(&self, _image: &Path) -> Result<Vec<Option<u8>>, OcrError> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[test]
+    fn recognized_cells_load_as_a_grid() {
+        let puzzle = "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+        let cells = puzzle.chars().map(|ch| ch.to_digit(10).filter(|&d| d != 0).map(|d| d as u8)).collect();
+
+        let matrix = import_from_image(&MockOcr(cells), Path::new("photo.png")).unwrap();
+        assert_eq!(matrix, parse_puzzle(puzzle).unwrap());
+
+        // A misread 5 next to the first one
+        let mut misread: Vec<Option<u8>> = vec![None; 81];
+        misread[0] = Some(5);
+        misread[1] = Some(5);
+        assert!(matches!(import_from_image(&MockOcr(misread), Path::new("photo.png")), Err(OcrError::Conflicts(_))));
+        assert!(matches!(import_from_image(&MockOcr(vec![None; 80]), Path::new("photo.png")), Err(OcrError::InvalidGrid(_))));
+    }
+}
@@ -0,0 +1,173 @@
+use std::{fs, io, path::Path};
+
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str};
+
+use super::solve_sat;
+
+// A4 in points, as used by pdf-writer.
+const PAGE_WIDTH: f32 = 595.;
+const PAGE_HEIGHT: f32 = 842.;
+const MARGIN: f32 = 40.;
+const TITLE_HEIGHT: f32 = 24.;
+const FONT: Name = Name(b"F1");
+
+// Title and grid of one slot of a page.
+type Sheet = (String, Vec<Vec<i8>>);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PdfOptions {
+    pub puzzles_per_page: usize,
+    pub include_solutions: bool, // Solutions follow the puzzles, on their own pages with the same layout
+}
+
+impl Default for PdfOptions {
+    fn default() -> Self {
+        PdfOptions {puzzles_per_page: 1, include_solutions: false}
+    }
+}
+
+pub fn page_count(puzzles: usize, options: &PdfOptions) -> usize {
+    let pages = puzzles.div_ceil(options.puzzles_per_page.max(1));
+    if options.include_solutions {pages * 2} else {pages}
+}
+
+/*
+    Printable worksheet: puzzles laid out `puzzles_per_page` to an A4 page (two columns past the first one),
+    optionally followed by their solutions. Unsolvable puzzles get an empty solution grid.
+*/
+pub fn worksheet_pdf(puzzles: &[Vec<Vec<i8>>], options: &PdfOptions) -> Vec<u8> {
+
+    let per_page = options.puzzles_per_page.max(1);
+
+    let mut sheets: Vec<Sheet> = puzzles.iter()
+        .enumerate()
+        .map(|(i, puzzle)| (format!("Puzzle {}", i + 1), puzzle.clone()))
+        .collect();
+
+    if options.include_solutions {
+        for (i, puzzle) in puzzles.iter().enumerate() {
+            let mut solution = puzzle.clone();
+
+            if solve_sat(&mut solution) {
+                sheets.push((format!("Solution {}", i + 1), solution));
+            } else {
+                sheets.push((format!("Solution {} (unsolvable)", i + 1), vec![vec![0; puzzle.len()]; puzzle.len()]));
+            }
+        }
+    }
+
+    // Solutions start on a fresh page.
+    let mut pages: Vec<&[Sheet]> = sheets[..puzzles.len()].chunks(per_page).collect();
+    pages.extend(sheets[puzzles.len()..].chunks(per_page));
+
+    let mut pdf = Pdf::new();
+    let catalog_id = Ref::new(1);
+    let page_tree_id = Ref::new(2);
+    let font_id = Ref::new(3);
+    let page_ids: Vec<Ref> = (0..pages.len()).map(|i| Ref::new(4 + 2 * i as i32)).collect();
+
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id).kids(page_ids.iter().copied()).count(pages.len() as i32);
+    pdf.type1_font(font_id).base_font(Name(b"Helvetica"));
+
+    let cols = if per_page == 1 {1} else {2};
+    let rows = per_page.div_ceil(cols);
+    let slot_width = (PAGE_WIDTH - 2. * MARGIN) / cols as f32;
+    let slot_height = (PAGE_HEIGHT - 2. * MARGIN) / rows as f32;
+
+    for (page_id, sheets) in page_ids.iter().zip(pages) {
+        let content_id = Ref::new(page_id.get() + 1);
+
+        let mut page = pdf.page(*page_id);
+        page.media_box(Rect::new(0., 0., PAGE_WIDTH, PAGE_HEIGHT));
+        page.parent(page_tree_id);
+        page.contents(content_id);
+        page.resources().fonts().pair(FONT, font_id);
+        page.finish();
+
+        let mut content = Content::new();
+
+        for (i, (title, grid)) in sheets.iter().enumerate() {
+            let left = MARGIN + (i % cols) as f32 * slot_width;
+            let top = PAGE_HEIGHT - MARGIN - (i / cols) as f32 * slot_height;
+            draw_grid(&mut content, title, grid, left, top, slot_width.min(slot_height - TITLE_HEIGHT) - 10.);
+        }
+
+        pdf.stream(content_id, &content.finish());
+    }
+
+    pdf.finish()
+}
+
+pub fn export_pdf(puzzles: &[Vec<Vec<i8>>], options: &PdfOptions, path: &Path) -> io::Result<()> {
+    fs::write(path, worksheet_pdf(puzzles, options))
+}
+
+// Draws `title` and the grid below it, with the grid's top-left corner at (left, top - TITLE_HEIGHT).
+fn draw_grid(content: &mut Content, title: &str, grid: &[Vec<i8>], left: f32, top: f32, side: f32) {
+
+    let size = grid.len();
+    if size == 0 {return}
+
+    let sub_size = size.isqrt();
+    let cell = side / size as f32;
+    let bottom = top - TITLE_HEIGHT - side;
+
+    content.begin_text();
+    content.set_font(FONT, 14.);
+    content.next_line(left, top - 14.);
+    content.show(Str(title.as_bytes()));
+    content.end_text();
+
+    // Thin cell lines, thick block borders.
+    for i in 0..=size {
+        let offset = i as f32 * cell;
+        content.set_line_width(if sub_size > 0 && i % sub_size == 0 {2.} else {0.5});
+        content.move_to(left + offset, bottom).line_to(left + offset, bottom + side).stroke();
+        content.move_to(left, bottom + offset).line_to(left + side, bottom + offset).stroke();
+    }
+
+    let font_size = cell * 0.55;
+
+    for (row, values) in grid.iter().enumerate() {
+        for (col, &value) in values.iter().enumerate() {
+            if value == 0 {continue}
+
+            let text = value.to_string();
+            // Helvetica digits are 0.556 em wide.
+            let x = left + col as f32 * cell + (cell - 0.556 * font_size * text.len() as f32) / 2.;
+            let y = bottom + (size - 1 - row) as f32 * cell + (cell - 0.7 * font_size) / 2.;
+
+            content.begin_text();
+            content.set_font(FONT, font_size);
+            content.next_line(x, y);
+            content.show(Str(text.as_bytes()));
+            content.end_text();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sudoku::parse_puzzle;
+
+    // Page objects in the file, the page tree ("/Type /Pages") excluded.
+    fn pages_in(pdf: &[u8]) -> usize {
+        let text = String::from_utf8_lossy(pdf);
+        text.matches("/Type /Page").count() - text.matches("/Type /Pages").count()
+    }
+
+    #[test]
+    fn worksheets_have_the_expected_pages() {
+        let puzzles = vec![parse_puzzle("1204301221004301").unwrap(); 3];
+        let options = PdfOptions {puzzles_per_page: 2, include_solutions: true};
+
+        let pdf = worksheet_pdf(&puzzles, &options);
+
+        assert!(pdf.starts_with(b"%PDF"));
+        assert_eq!(page_count(3, &options), 4);
+        assert_eq!(pages_in(&pdf), 4);
+        assert_eq!(pages_in(&worksheet_pdf(&puzzles, &PdfOptions::default())), 3);
+    }
+}
@@ -0,0 +1,123 @@
+use std::{fmt, path::Path};
+
+use super::{ParseError, export_puzzle_to_string, parse_puzzle};
+
+/*
+    QR codes for sharing puzzles with phones: the single-line puzzle string at error correction level M.
+    Encoding is done by the qrcode crate, decoding by rqrr, which also finds codes in photos and screenshots.
+*/
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QrCode {
+    modules: Vec<Vec<bool>>, // Row by row, true for dark
+}
+
+// Modules of light margin around the symbol, as the standard asks for.
+pub const QUIET_ZONE: usize = 4;
+
+impl QrCode {
+    // Smallest version that holds `text`.
+    pub fn encode(text: &str) -> Result<Self, QrError> {
+        let code = qrcode::QrCode::with_error_correction_level(text, qrcode::EcLevel::M)
+            .map_err(|_| QrError::TooLong(text.len()))?;
+
+        let size = code.width();
+        let modules = (0..size)
+            .map(|y| (0..size).map(|x| code[(x, y)] == qrcode::Color::Dark).collect())
+            .collect();
+
+        Ok(Self {modules})
+    }
+
+    // Modules per side.
+    pub fn size(&self) -> usize {
+        self.modules.len()
+    }
+
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        self.modules[y][x]
+    }
+
+    // Black and white image with `scale` pixels per module, quiet zone included.
+    pub fn to_image(&self, scale: u32) -> image::GrayImage {
+        let side = (self.size() + 2 * QUIET_ZONE) as u32 * scale;
+
+        image::GrayImage::from_fn(side, side, |px, py| {
+            let (x, y) = ((px / scale) as usize, (py / scale) as usize);
+            let inside = (QUIET_ZONE..QUIET_ZONE + self.size()).contains(&x) && (QUIET_ZONE..QUIET_ZONE + self.size()).contains(&y);
+
+            image::Luma([if inside && self.is_dark(x - QUIET_ZONE, y - QUIET_ZONE) {0} else {255}])
+        })
+    }
+
+    pub fn save_png(&self, path: &Path, scale: u32) -> Result<(), QrError> {
+        self.to_image(scale).save(path).map_err(|err| QrError::Image(err.to_string()))
+    }
+}
+
+// Text of the first QR code found in the picture.
+pub fn decode_image(image: &image::GrayImage) -> Result<String, QrError> {
+    let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(image.width() as usize, image.height() as usize, |x, y| image.get_pixel(x as u32, y as u32)[0]);
+
+    let grid = prepared.detect_grids().into_iter().next().ok_or(QrError::NotFound)?;
+    let (_, text) = grid.decode().map_err(|err| QrError::Unreadable(err.to_string()))?;
+
+    Ok(text)
+}
+
+// The puzzle as a QR code of its single-line string.
+pub fn puzzle_to_qr(matrix: &[Vec<i8>]) -> Result<QrCode, QrError> {
+    QrCode::encode(&export_puzzle_to_string(matrix))
+}
+
+pub fn puzzle_from_qr_image(path: &Path) -> Result<Vec<Vec<i8>>, QrError> {
+    let image = image::open(path).map_err(|err| QrError::Image(err.to_string()))?.to_luma8();
+
+    parse_puzzle(&decode_image(&image)?).map_err(QrError::InvalidGrid)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QrError {
+    TooLong(usize),          // Bytes past the capacity of version 40
+    NotFound,                // No QR code in the picture
+    Unreadable(String),
+    Image(String),           // Loading or saving the picture failed
+    InvalidGrid(ParseError), // Decoded text that isn't a puzzle
+}
+
+impl fmt::Display for QrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QrError::TooLong(len) => write!(f, "{} bytes don't fit in a QR code", len),
+            QrError::NotFound => write!(f, "no QR code found in the image"),
+            QrError::Unreadable(reason) => write!(f, "unreadable QR code: {}", reason),
+            QrError::Image(err) => write!(f, "image error: {}", err),
+            QrError::InvalidGrid(err) => write!(f, "QR code doesn't hold a puzzle: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for QrError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn puzzle_round_trips_through_a_qr_image() {
+        let puzzle = parse_puzzle("530070000600195000098000060800060003400803001700020006060000280000419005000080079").unwrap();
+
+        let code = puzzle_to_qr(&puzzle).unwrap();
+        let text = decode_image(&code.to_image(4)).unwrap();
+
+        assert_eq!(parse_puzzle(&text).unwrap(), puzzle);
+    }
+
+    #[test]
+    fn a_25x25_puzzle_fits_in_a_qr_code() {
+        let puzzle = vec![vec![25; 25]; 25];
+
+        let code = puzzle_to_qr(&puzzle).unwrap();
+
+        assert_eq!(parse_puzzle(&decode_image(&code.to_image(4)).unwrap()).unwrap(), puzzle);
+    }
+}
@@ -0,0 +1,118 @@
+use std::fmt;
+
+use super::{assert_valid_size, units};
+
+/*
+    Jigsaw Sudoku: the blocks are replaced by irregular regions, `size` of them with `size` cells each.
+    Rows and columns keep their usual rule.
+*/
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Regions {
+    cells: Vec<Vec<(usize, usize)>>,
+    region_of: Vec<usize>, // Region index of every cell, row by row
+}
+
+impl Regions {
+    // Checks that `cells` partitions a board: as many regions as cells per region, every cell in exactly one.
+    pub fn new(cells: Vec<Vec<(usize, usize)>>) -> Result<Self, RegionError> {
+
+        let size = cells.len();
+        assert_valid_size(size).map_err(|_| RegionError::RegionCount(size))?;
+
+        let mut region_of: Vec<Option<usize>> = vec![None; size * size];
+
+        for (region, region_cells) in cells.iter().enumerate() {
+            if region_cells.len() != size {
+                return Err(RegionError::RegionSize {region, len: region_cells.len()});
+            }
+
+            for &(row, col) in region_cells {
+                if row >= size || col >= size {return Err(RegionError::OutOfBounds((row, col)))}
+
+                let slot = &mut region_of[row * size + col];
+                if slot.is_some() {return Err(RegionError::Overlap((row, col)))}
+                *slot = Some(region);
+            }
+        }
+
+        // Every cell is covered: size regions of size distinct in-bounds cells.
+        Ok(Self {cells, region_of: region_of.into_iter().flatten().collect()})
+    }
+
+    /*
+        Region map: one character per cell, row by row, cells with the same character forming a region.
+        Any non-whitespace character works as a label; regions are numbered by first appearance. E.g. for 4x4:
+            AAAB
+            CABB
+            CCDB
+            CDDD
+        Whitespace is ignored, so the map can be written on one line or one line per row.
+    */
+    pub fn from_map(text: &str) -> Result<Self, RegionError> {
+
+        let labels: Vec<char> = text.chars().filter(|ch| !ch.is_whitespace()).collect();
+
+        let size = labels.len().isqrt();
+        if size * size != labels.len() {return Err(RegionError::InvalidLength(labels.len()))}
+
+        let mut names: Vec<char> = Vec::new();
+        let mut cells: Vec<Vec<(usize, usize)>> = Vec::new();
+
+        for (i, label) in labels.into_iter().enumerate() {
+            let region = names.iter().position(|&name| name == label).unwrap_or_else(|| {
+                names.push(label);
+                cells.push(Vec::new());
+                names.len() - 1
+            });
+
+            cells[region].push((i / size, i % size));
+        }
+
+        Self::new(cells)
+    }
+
+    // The usual boxes, as regions: a jigsaw board with these regions is plain Sudoku.
+    pub fn boxes(size: usize) -> Self {
+        Self::new(units(size).split_off(2 * size)).expect("Boxes always partition a valid board")
+    }
+
+    pub fn size(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn cells(&self) -> &[Vec<(usize, usize)>] {
+        &self.cells
+    }
+
+    pub fn region_of(&self, pos: (usize, usize)) -> usize {
+        self.region_of[pos.0 * self.size() + pos.1]
+    }
+
+    // Cells sharing a region with `pos`, `pos` included.
+    pub fn region_cells(&self, pos: (usize, usize)) -> &[(usize, usize)] {
+        &self.cells[self.region_of(pos)]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegionError {
+    InvalidLength(usize),                 // Map characters that don't form a square board
+    RegionCount(usize),                   // Not a valid board size
+    RegionSize {region: usize, len: usize},
+    OutOfBounds((usize, usize)),
+    Overlap((usize, usize)),              // Cell listed in two regions
+}
+
+impl fmt::Display for RegionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegionError::InvalidLength(len) => write!(f, "{} cells do not form a square board", len),
+            RegionError::RegionCount(count) => write!(f, "{} regions do not make a valid board", count),
+            RegionError::RegionSize {region, len} => write!(f, "region {} has {} cells", region + 1, len),
+            RegionError::OutOfBounds((row, col)) => write!(f, "cell ({}, {}) is outside the board", row, col),
+            RegionError::Overlap((row, col)) => write!(f, "cell ({}, {}) is in more than one region", row, col),
+        }
+    }
+}
+
+impl std::error::Error for RegionError {}
@@ -0,0 +1,201 @@
+use std::fmt;
+use varisat::{CnfFormula, ExtendFormula, Lit, Solver};
+
+use super::{SizeError, assert_valid_board, assert_valid_size, index_to_value, sudoku_to_sat};
+
+/*
+    Samurai Sudoku: five grids of side `size`, the center one sharing a corner block with each of the four others.
+    For 9x9 grids they sit on a 21x21 canvas:
+        0 0 0 . 1 1 1
+        0 0 0 . 1 1 1
+        0 0 2 2 2 1 1
+        . . 2 2 2 . .
+        3 3 2 2 2 4 4
+        3 3 3 . 4 4 4
+        3 3 3 . 4 4 4
+    (one character per block). A shared cell belongs to both its grids, so it keeps one value for both.
+*/
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Samurai {
+    size: usize,
+    cells: Vec<Vec<i8>>, // The whole canvas, 0 for blanks and for the gaps outside the grids
+}
+
+impl Samurai {
+    pub const GRIDS: usize = 5;
+
+    pub fn new(size: usize) -> Result<Self, SizeError> {
+        assert_valid_size(size)?;
+
+        let canvas = canvas_size(size);
+        Ok(Self {size, cells: vec![vec![0; canvas]; canvas]})
+    }
+
+    // Assembles the five grids, in offsets() order; grids sharing a block must agree on it.
+    pub fn from_grids(grids: &[Vec<Vec<i8>>; Samurai::GRIDS]) -> Result<Self, SamuraiError> {
+
+        for grid in grids {
+            assert_valid_board(grid).map_err(SamuraiError::InvalidGrid)?;
+        }
+
+        let size = grids[0].len();
+        if let Some(grid) = grids.iter().position(|grid| grid.len() != size) {
+            return Err(SamuraiError::SizeMismatch {grid, len: grids[grid].len()});
+        }
+
+        let mut samurai = Self::new(size).map_err(SamuraiError::InvalidGrid)?;
+
+        for (grid, offset) in grids.iter().zip(samurai.offsets()) {
+            for (row, values) in grid.iter().enumerate() {
+                for (col, &value) in values.iter().enumerate() {
+                    let pos = (offset.0 + row, offset.1 + col);
+                    let current = samurai.cells[pos.0][pos.1];
+
+                    if current != 0 && value != 0 && current != value {return Err(SamuraiError::Overlap(pos))}
+                    if value != 0 {samurai.cells[pos.0][pos.1] = value}
+                }
+            }
+        }
+
+        Ok(samurai)
+    }
+
+    // Side of each grid.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn cells(&self) -> &[Vec<i8>] {
+        &self.cells
+    }
+
+    // Top-left canvas cell of each grid, in reading order: top-left, top-right, center, bottom-left, bottom-right.
+    pub fn offsets(&self) -> [(usize, usize); Samurai::GRIDS] {
+        let far = 2 * (self.size - self.size.isqrt());
+        let center = far / 2;
+
+        [(0, 0), (0, far), (center, center), (far, 0), (far, far)]
+    }
+
+    // Whether the canvas cell is part of a grid (and not one of the gaps between the corner grids).
+    pub fn contains(&self, pos: (usize, usize)) -> bool {
+        self.offsets().iter().any(|&(row, col)| (row..row + self.size).contains(&pos.0) && (col..col + self.size).contains(&pos.1))
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> i8 {
+        self.cells[row][col]
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, value: i8) {
+        self.cells[row][col] = value;
+    }
+
+    // Copy of one grid, `grid` indexing offsets().
+    pub fn grid(&self, grid: usize) -> Vec<Vec<i8>> {
+        let (row, col) = self.offsets()[grid];
+
+        self.cells[row..row + self.size].iter().map(|values| values[col..col + self.size].to_vec()).collect()
+    }
+}
+
+fn canvas_size(size: usize) -> usize {
+    3 * size - 2 * size.isqrt()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SamuraiError {
+    InvalidGrid(SizeError),
+    SizeMismatch {grid: usize, len: usize}, // Grid not the size of the first one
+    Overlap((usize, usize)),                // Canvas cell given different values by two grids
+}
+
+impl fmt::Display for SamuraiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SamuraiError::InvalidGrid(err) => write!(f, "invalid grid: {}", err),
+            SamuraiError::SizeMismatch {grid, len} => write!(f, "grid {} is {}x{}, unlike the first one", grid + 1, len, len),
+            SamuraiError::Overlap((row, col)) => write!(f, "shared cell ({}, {}) has two different values", row, col),
+        }
+    }
+}
+
+impl std::error::Error for SamuraiError {}
+
+/*
+    Each grid is encoded by sudoku_to_sat on its own, then its variables are moved from grid coordinates to canvas ones.
+    Shared cells thus end up with the same variables in both grids, which is all the overlap needs.
+    Variables are laid out like lit_from_indx's, over the canvas: n + size * (col + canvas * row).
+*/
+pub fn samurai_to_sat(samurai: &Samurai) -> CnfFormula {
+
+    let size = samurai.size();
+    let canvas = canvas_size(size);
+
+    let mut formula = CnfFormula::new();
+
+    for (grid, (row_offset, col_offset)) in samurai.offsets().into_iter().enumerate() {
+        for clause in sudoku_to_sat(&samurai.grid(grid)).iter() {
+            let moved: Vec<Lit> = clause.iter()
+                .map(|lit| {
+                    let index = lit.index();
+                    let (row, col, n) = (index / (size * size), index / size % size, index % size);
+
+                    Lit::from_index(n + size * (col + col_offset + canvas * (row + row_offset)), lit.is_positive())
+                })
+                .collect();
+
+            formula.add_clause(&moved);
+        }
+    }
+
+    formula
+}
+
+// Fills every grid of the Samurai, false (and untouched) if they can't all be solved together.
+pub fn solve_samurai(samurai: &mut Samurai) -> bool {
+
+    let size = samurai.size();
+    let canvas = canvas_size(size);
+
+    let mut solver = Solver::new();
+    solver.add_formula(&samurai_to_sat(samurai));
+
+    if !solver.solve().unwrap() {return false}
+
+    // Variables of the gaps appear in no clause, whatever value the solver gave them is meaningless.
+    for lit in solver.model().unwrap().into_iter().filter(|lit| lit.is_positive()) {
+        let index = lit.index();
+        let (row, col, n) = (index / (size * canvas), index / size % canvas, index % size);
+
+        if samurai.contains((row, col)) {samurai.set(row, col, index_to_value(n))}
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sudoku::{is_complete_solution, solve_sat};
+
+    #[test]
+    fn overlapping_grids_share_their_corner_block() {
+        // 4x4 grids on an 8x8 canvas: the top-left grid's 1s at (0, 3) and (3, 0) leave (2, 2) as the only spot
+        // for the 1 of its bottom-right block, which is also the center grid's top-left block.
+        let mut samurai = Samurai::new(4).unwrap();
+        samurai.set(0, 3, 1);
+        samurai.set(3, 0, 1);
+
+        let mut solved = samurai.clone();
+        assert!(solve_samurai(&mut solved));
+        assert_eq!(solved.get(2, 2), 1);
+        assert!((0..Samurai::GRIDS).all(|grid| is_complete_solution(&solved.grid(grid))));
+
+        // A 1 lower in the center grid's first column is fine for each grid alone, not for both together
+        samurai.set(4, 2, 1);
+        for grid in [0, 2, 3] {
+            assert!(solve_sat(&mut samurai.grid(grid)), "grid {}", grid);
+        }
+        assert!(!solve_samurai(&mut samurai.clone()));
+    }
+}
@@ -0,0 +1,100 @@
+use std::io;
+
+use super::{count_solutions, export_puzzle_to_string, parse_puzzle, solve_sat};
+
+// /count stops counting here, so a near-empty grid answers quickly.
+pub const MAX_COUNTED_SOLUTIONS: usize = 1000;
+
+/*
+    Minimal HTTP front end for scripts and web pages, started with `sudoku --serve <port>`.
+    Both endpoints take a puzzle string (see parse_puzzle) as the request body and answer in plain text:
+        POST /solve -> the solved puzzle string
+        POST /count -> the number of solutions, up to MAX_COUNTED_SOLUTIONS
+    Requests are served one at a time.
+*/
+pub fn serve(port: u16) -> io::Result<()> {
+
+    let server = tiny_http::Server::http(("127.0.0.1", port)).map_err(io::Error::other)?;
+    println!("Listening on http://127.0.0.1:{}", port);
+
+    for mut request in server.incoming_requests() {
+        let mut body = String::new();
+
+        let (status, text) = match request.as_reader().read_to_string(&mut body) {
+            Ok(_) => handle(request.method().as_str(), request.url(), &body),
+            Err(err) => (400, format!("Unreadable body: {}", err)),
+        };
+
+        if let Err(err) = request.respond(tiny_http::Response::from_string(text).with_status_code(status)) {
+            println!("Could not send response: {}", err);
+        }
+    }
+
+    Ok(())
+}
+
+// Status code and body for a request, kept apart from the server so it can be called directly.
+pub fn handle(method: &str, path: &str, body: &str) -> (u16, String) {
+
+    if path != "/solve" && path != "/count" {return (404, "Not found".to_string())}
+    if method != "POST" {return (405, "Use POST".to_string())}
+
+    let mut matrix = match parse_puzzle(body) {
+        Ok(matrix) => matrix,
+        Err(err) => return (400, format!("Invalid puzzle: {}", err)),
+    };
+
+    if path == "/count" {
+        return (200, count_solutions(&matrix, MAX_COUNTED_SOLUTIONS).to_string());
+    }
+
+    if solve_sat(&mut matrix) {
+        (200, export_puzzle_to_string(&matrix))
+    } else {
+        (422, "Puzzle is unsolvable".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{io::{Read, Write}, net::{TcpListener, TcpStream}, thread, time::Duration};
+
+    const PUZZLE: &str = "1204301221004301";
+
+    // Sends a raw HTTP/1.0 request, so the server closes the connection after answering.
+    fn post(port: u16, path: &str, body: &str) -> String {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        write!(stream, "POST {} HTTP/1.0\r\nContent-Length: {}\r\n\r\n{}", path, body.len(), body).unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn handlers_answer_solve_and_count() {
+        assert_eq!(handle("POST", "/solve", PUZZLE), (200, "1234341221434321".to_string()));
+        assert_eq!(handle("POST", "/count", "0034341200434321"), (200, "2".to_string()));
+        assert_eq!(handle("GET", "/solve", PUZZLE).0, 405);
+        assert_eq!(handle("POST", "/other", PUZZLE).0, 404);
+        assert_eq!(handle("POST", "/solve", "12x").0, 400);
+        assert_eq!(handle("POST", "/solve", "1100000000000000").0, 422);
+    }
+
+    #[test]
+    fn the_server_solves_over_http() {
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        thread::spawn(move || serve(port));
+
+        // Wait for the server to listen
+        for _ in 0..50 {
+            if TcpStream::connect(("127.0.0.1", port)).is_ok() {break}
+            thread::sleep(Duration::from_millis(20));
+        }
+
+        let response = post(port, "/solve", PUZZLE);
+        assert!(response.starts_with("HTTP/1.0 200") || response.starts_with("HTTP/1.1 200"), "{}", response);
+        assert!(response.ends_with("1234341221434321"));
+    }
+}
@@ -0,0 +1,169 @@
+/*
+    Puzzle equivalence under the validity-preserving transforms: transposition (with row/column
+    permutations this covers rotations and reflections), band and stack permutations,
+    row permutations inside a band, column permutations inside a stack and digit relabeling.
+*/
+
+// True if `b` can be obtained from `a` by some combination of the transforms above.
+pub fn are_isomorphic(a: &[Vec<i8>], b: &[Vec<i8>]) -> bool {
+
+    let size = a.len();
+    if b.len() != size || digit_profile(a) != digit_profile(b) {return false}
+
+    let transposed: Vec<Vec<i8>> = (0..size).map(|col| a.iter().map(|row| row[col]).collect()).collect();
+
+    [a, transposed.as_slice()].into_iter().any(|source| IsomorphismSearch::new(source, b).match_rows(0))
+}
+
+// Blank count plus the sorted clue count of every digit: relabeling can't change it.
+fn digit_profile(matrix: &[Vec<i8>]) -> (usize, Vec<usize>) {
+
+    let mut counts = vec![0usize; matrix.len() + 1];
+    for &value in matrix.iter().flatten() {
+        counts[value as usize] += 1;
+    }
+
+    let blanks = counts.remove(0);
+    counts.sort_unstable();
+
+    (blanks, counts)
+}
+
+/*
+    Backtracking over row orders, then column orders, of `source`.
+    Columns are checked as soon as they are placed, building the digit relabeling along the way,
+    so most column orders are discarded after a few cells.
+*/
+struct IsomorphismSearch<'a> {
+    source: &'a [Vec<i8>],
+    target: &'a [Vec<i8>],
+    sub_size: usize,
+    rows: Vec<usize>, // rows[r]: source row moved to row r
+    cols: Vec<usize>, // cols[c]: source column moved to column c
+    forward: Vec<i8>, // Source digit -> target digit (0 while unassigned)
+    backward: Vec<i8>,
+}
+
+impl<'a> IsomorphismSearch<'a> {
+    fn new(source: &'a [Vec<i8>], target: &'a [Vec<i8>]) -> Self {
+        let size = source.len();
+
+        Self {
+            source,
+            target,
+            sub_size: size.isqrt(),
+            rows: Vec::with_capacity(size),
+            cols: Vec::with_capacity(size),
+            forward: vec![0; size + 1],
+            backward: vec![0; size + 1],
+        }
+    }
+
+    fn match_rows(&mut self, row: usize) -> bool {
+
+        if row == self.source.len() {return self.match_cols(0)}
+
+        let clues = |line: &[i8]| line.iter().filter(|&&v| v != 0).count();
+
+        for candidate in lines_available(&self.rows, self.sub_size) {
+            if clues(&self.source[candidate]) != clues(&self.target[row]) {continue}
+
+            self.rows.push(candidate);
+            if self.match_rows(row + 1) {return true}
+            self.rows.pop();
+        }
+
+        false
+    }
+
+    fn match_cols(&mut self, col: usize) -> bool {
+
+        if col == self.source.len() {return true}
+
+        for candidate in lines_available(&self.cols, self.sub_size) {
+            let mut assigned: Vec<i8> = Vec::new();
+
+            if self.assign_column(candidate, col, &mut assigned) {
+                self.cols.push(candidate);
+                if self.match_cols(col + 1) {return true}
+                self.cols.pop();
+            }
+
+            for digit in assigned {
+                self.backward[self.forward[digit as usize] as usize] = 0;
+                self.forward[digit as usize] = 0;
+            }
+        }
+
+        false
+    }
+
+    // Extends the relabeling with the digits of the column; the new source digits go in `assigned` for undoing.
+    fn assign_column(&mut self, source_col: usize, col: usize, assigned: &mut Vec<i8>) -> bool {
+
+        for (row, &source_row) in self.rows.iter().enumerate() {
+            let x = self.source[source_row][source_col];
+            let y = self.target[row][col];
+
+            if (x == 0) != (y == 0) {return false}
+            if x == 0 {continue}
+
+            if self.forward[x as usize] == 0 && self.backward[y as usize] == 0 {
+                self.forward[x as usize] = y;
+                self.backward[y as usize] = x;
+                assigned.push(x);
+            } else if self.forward[x as usize] != y {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/*
+    Source lines that can go next, given the ones already `placed`:
+    at the start of a band any line of an unused band, otherwise the unused lines of the current band.
+*/
+fn lines_available(placed: &[usize], sub_size: usize) -> Vec<usize> {
+
+    let size = sub_size * sub_size;
+
+    if placed.len().is_multiple_of(sub_size) {
+        let used_bands: Vec<usize> = placed.iter().map(|line| line / sub_size).collect();
+        (0..size).filter(|line| !used_bands.contains(&(line / sub_size))).collect()
+    } else {
+        let band = placed[placed.len() - placed.len() % sub_size] / sub_size;
+        (band * sub_size..(band + 1) * sub_size).filter(|line| !placed.contains(line)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sudoku::{parse_puzzle, swap_digits};
+
+    const PUZZLE: &str = "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+
+    #[test]
+    fn transformed_puzzles_are_isomorphic() {
+        let puzzle = parse_puzzle(PUZZLE).unwrap();
+
+        // Rotated a quarter turn, 1 and 9 swapped, then the first two bands swapped
+        let mut transformed: Vec<Vec<i8>> = (0..9).map(|row| (0..9).map(|col| puzzle[8 - col][row]).collect()).collect();
+        swap_digits(&mut transformed, 1, 9);
+        transformed.rotate_left(3);
+
+        assert_ne!(transformed, puzzle);
+        assert!(are_isomorphic(&puzzle, &transformed));
+        assert!(are_isomorphic(&transformed, &puzzle));
+
+        // Same clues per digit, but the first 5 moved to the other side of the 3
+        let mut moved = puzzle.clone();
+        moved[0].swap(0, 2);
+        assert!(!are_isomorphic(&puzzle, &moved));
+
+        let unrelated = parse_puzzle("000000000000003085001020000000507000004000100090000000500000073002010000000040009").unwrap();
+        assert!(!are_isomorphic(&puzzle, &unrelated));
+    }
+}
@@ -0,0 +1,695 @@
+use std::fmt;
+use super::{Board, ConstraintSet};
+
+/*
+    Human-style solving: instead of brute force, fill cells with the simplest technique that applies
+    and only guess when no technique makes progress.
+    X-Wings and Swordfish don't fill cells themselves: they strike candidates until a single appears.
+*/
+
+// Ordered from easiest to hardest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Technique {
+    NakedSingle,  // The cell has a single candidate left
+    HiddenSingle, // The value fits in only one cell of a row, column or block
+    XWing,        // A single that only shows up once X-Wing eliminations are made
+    Swordfish,    // A single that only shows up once Swordfish eliminations are made
+    Guess,        // Nothing applies: try a candidate (and backtrack if it leads nowhere)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SolveStep {
+    pub technique: Technique,
+    pub pos: (usize, usize),
+    pub value: i8,
+}
+
+pub fn compute_candidates(matrix: &[Vec<i8>]) -> Vec<Vec<Vec<i8>>> {
+    let board = Board::from_matrix(matrix);
+    let size = board.size();
+
+    (0..size).map(|row| (0..size).map(|col| board.candidates((row, col))).collect()).collect()
+}
+
+/*
+    Returns the steps that lead from `matrix` to its solution (replaying them in order fills the grid),
+    or None if the puzzle has no solution.
+    Wrong guesses are discarded, so every Guess in the list is part of the final solution.
+*/
+pub fn solve_with_steps(matrix: &[Vec<i8>]) -> Option<Vec<SolveStep>> {
+
+    let mut board = Board::from_matrix(matrix);
+    let size = board.size();
+
+    for row in 0..size {
+        for col in 0..size {
+            let value = board.get(row, col);
+            if value != 0 && !board.is_value_valid(value, (row, col)) {return None}
+        }
+    }
+
+    // Guess points to come back to: board and struck candidates before the guess, steps made until then, candidates not tried yet.
+    struct Branch {
+        board: Board,
+        struck: Vec<Vec<i8>>,
+        steps_len: usize,
+        pos: (usize, usize),
+        untried: Vec<i8>,
+    }
+
+    let mut branches: Vec<Branch> = Vec::new();
+    let mut steps: Vec<SolveStep> = Vec::new();
+
+    // Candidates removed by fish eliminations, per cell, and the hardest fish used since the last placement.
+    let mut struck: Vec<Vec<i8>> = vec![Vec::new(); size * size];
+    let mut fish: Option<Technique> = None;
+
+    loop {
+        match next_step(&board, &struck) {
+            Progress::Solved => return Some(steps),
+            Progress::Step(mut step) => {
+                // The placement needed the eliminations, so it takes their technique.
+                step.technique = step.technique.max(fish.take().unwrap_or(step.technique));
+                board.set(step.pos.0, step.pos.1, step.value);
+                steps.push(step);
+            }
+            Progress::Eliminate(technique, eliminations) => {
+                for elimination in eliminations {
+                    for (r, c) in elimination.cells {
+                        struck[r * size + c].push(elimination.value);
+                    }
+                }
+                fish = fish.max(Some(technique));
+            }
+            Progress::Stuck(pos, mut untried) => {
+                let value = untried.remove(0);
+                branches.push(Branch {board: board.clone(), struck: struck.clone(), steps_len: steps.len(), pos, untried});
+                board.set(pos.0, pos.1, value);
+                steps.push(SolveStep {technique: Technique::Guess, pos, value});
+                fish = None;
+            }
+            Progress::DeadEnd => {
+                // Resume from the most recent guess that still has untried candidates.
+                loop {
+                    let branch = branches.last_mut()?;
+
+                    if branch.untried.is_empty() {
+                        branches.pop();
+                        continue;
+                    }
+
+                    let value = branch.untried.remove(0);
+                    board = branch.board.clone();
+                    struck = branch.struck.clone();
+                    fish = None;
+                    steps.truncate(branch.steps_len);
+                    board.set(branch.pos.0, branch.pos.1, value);
+                    steps.push(SolveStep {technique: Technique::Guess, pos: branch.pos, value});
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/*
+    Hardest technique needed to solve `matrix`, None if there is no solution.
+    The step solver always picks the easiest technique that applies, so its hardest step is also the hardest one required.
+*/
+pub fn hardest_technique(matrix: &[Vec<i8>]) -> Option<Technique> {
+    let steps = solve_with_steps(matrix)?;
+
+    Some(steps.iter().map(|step| step.technique).max().unwrap_or(Technique::NakedSingle))
+}
+
+enum Progress {
+    Solved,
+    Step(SolveStep),
+    Eliminate(Technique, Vec<Elimination>), // No single yet, but the fish strikes some candidates
+    Stuck((usize, usize), Vec<i8>), // Cell with the fewest candidates, to guess on
+    DeadEnd,
+}
+
+fn next_step(board: &Board, struck: &[Vec<i8>]) -> Progress {
+
+    let size = board.size();
+    let candidates: Vec<Vec<i8>> = (0..size * size)
+        .map(|i| board.candidates((i / size, i % size)).into_iter().filter(|value| !struck[i].contains(value)).collect())
+        .collect();
+
+    let mut fewest: Option<usize> = None;
+
+    // Naked singles (also spots empty cells without candidates)
+    for (i, cands) in candidates.iter().enumerate() {
+        if board.get(i / size, i % size) != 0 {continue}
+
+        match cands.len() {
+            0 => return Progress::DeadEnd,
+            1 => return Progress::Step(SolveStep {technique: Technique::NakedSingle, pos: (i / size, i % size), value: cands[0]}),
+            len => if fewest.is_none_or(|f| len < candidates[f].len()) {fewest = Some(i)},
+        }
+    }
+
+    let Some(fewest) = fewest else {return Progress::Solved};
+
+    // Hidden singles
+    for unit in units(size) {
+        for value in 1..=size as i8 {
+            if unit.iter().any(|&(r, c)| board.get(r, c) == value) {continue}
+
+            let mut spots = unit.iter().filter(|&&(r, c)| candidates[r * size + c].contains(&value));
+
+            match (spots.next(), spots.next()) {
+                (None, _) => return Progress::DeadEnd, // Value can't be placed anywhere in the unit
+                (Some(&pos), None) => return Progress::Step(SolveStep {technique: Technique::HiddenSingle, pos, value}),
+                _ => {}
+            }
+        }
+    }
+
+    // Fish, easiest first
+    let eliminations = x_wing_eliminations(&candidates, size);
+    if !eliminations.is_empty() {return Progress::Eliminate(Technique::XWing, eliminations)}
+
+    let eliminations = swordfish_eliminations(&candidates, size);
+    if !eliminations.is_empty() {return Progress::Eliminate(Technique::Swordfish, eliminations)}
+
+    Progress::Stuck((fewest / size, fewest % size), candidates[fewest].clone())
+}
+
+// Every row, column and block as a list of cell positions.
+pub(crate) fn units(size: usize) -> Vec<Vec<(usize, usize)>> {
+
+    let sub_size = size.isqrt();
+    let mut units: Vec<Vec<(usize, usize)>> = Vec::with_capacity(size * 3);
+
+    for i in 0..size {
+        units.push((0..size).map(|j| (i, j)).collect());
+        units.push((0..size).map(|j| (j, i)).collect());
+    }
+
+    for br in 0..sub_size {
+        for bc in 0..sub_size {
+            units.push((0..size).map(|i| (br * sub_size + i / sub_size, bc * sub_size + i % sub_size)).collect());
+        }
+    }
+
+    units
+}
+
+// Two cells of the same unit whose only candidates are the same two values.
+// Those values can then be removed from every other cell of the unit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NakedPair {
+    pub cells: [(usize, usize); 2],
+    pub values: [i8; 2],
+}
+
+pub fn find_naked_pairs(matrix: &[Vec<i8>]) -> Vec<NakedPair> {
+
+    let board = Board::from_matrix(matrix);
+    let mut pairs: Vec<NakedPair> = Vec::new();
+
+    for unit in units(board.size()) {
+        let bivalue: Vec<((usize, usize), Vec<i8>)> = unit.iter()
+            .map(|&pos| (pos, board.candidates(pos)))
+            .filter(|(_, cands)| cands.len() == 2)
+            .collect();
+
+        for (i, (a, cands_a)) in bivalue.iter().enumerate() {
+            for (b, cands_b) in &bivalue[i + 1..] {
+                let pair = NakedPair {cells: [*a, *b], values: [cands_a[0], cands_a[1]]};
+
+                // Two cells sharing a row and a block are found twice
+                if cands_a == cands_b && !pairs.contains(&pair) {
+                    pairs.push(pair);
+                }
+            }
+        }
+    }
+
+    pairs
+}
+
+impl Technique {
+    pub const ALL: [Technique; 5] = [Technique::NakedSingle, Technique::HiddenSingle, Technique::XWing, Technique::Swordfish, Technique::Guess];
+
+    // Rough effort a human needs for one placement with this technique.
+    pub fn weight(self) -> f64 {
+        match self {
+            Technique::NakedSingle => 1.,
+            Technique::HiddenSingle => 2.,
+            Technique::XWing => 3.,
+            Technique::Swordfish => 4.,
+            Technique::Guess => 5.,
+        }
+    }
+}
+
+impl fmt::Display for Technique {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Technique::NakedSingle => write!(f, "Naked Single"),
+            Technique::HiddenSingle => write!(f, "Hidden Single"),
+            Technique::XWing => write!(f, "X-Wing"),
+            Technique::Swordfish => write!(f, "Swordfish"),
+            Technique::Guess => write!(f, "Guess"),
+        }
+    }
+}
+
+/*
+    Coarse difficulty score: the average technique weight over the solving path,
+    from 1.0 (naked singles only) to 5.0 (guessing all the way). None if there is no solution.
+*/
+pub fn rate_difficulty(matrix: &[Vec<i8>]) -> Option<f64> {
+    let steps = solve_with_steps(matrix)?;
+
+    if steps.is_empty() {return Some(Technique::NakedSingle.weight())}
+
+    Some(steps.iter().map(|step| step.technique.weight()).sum::<f64>() / steps.len() as f64)
+}
+
+// Rough guess of how long a backtracking solve takes, see estimate_difficulty_fast.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Estimate {
+    LikelyFast,
+    MayBeSlow,
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Estimate::LikelyFast => write!(f, "likely fast"),
+            Estimate::MayBeSlow => write!(f, "may be slow, consider SAT"),
+        }
+    }
+}
+
+/*
+    Cheap preview before a backtracking solve, without searching: naked singles are propagated,
+    then whatever is left decides. Solved by singles alone, or with at most a unit's worth
+    of cells left that all have one or two candidates, the search stays tiny; anything else may blow up.
+*/
+pub fn estimate_difficulty_fast(matrix: &[Vec<i8>]) -> Estimate {
+
+    let mut board = Board::from_matrix(matrix);
+    let size = board.size();
+
+    let remaining: Vec<usize> = loop {
+        let open: Vec<(usize, Vec<i8>)> = (0..size * size)
+            .filter(|&i| board.get(i / size, i % size) == 0)
+            .map(|i| (i, board.candidates((i / size, i % size))))
+            .collect();
+
+        // No solution: backtracking only finds out once every branch before that cell is exhausted.
+        if open.iter().any(|(_, cands)| cands.is_empty()) {return Estimate::MayBeSlow}
+
+        match open.iter().find(|(_, cands)| cands.len() == 1) {
+            Some(&(i, ref cands)) => board.set(i / size, i % size, cands[0]),
+            None => break open.into_iter().map(|(_, cands)| cands.len()).collect(),
+        }
+    };
+
+    if remaining.len() <= size && remaining.iter().all(|&len| len <= 2) {
+        Estimate::LikelyFast
+    } else {
+        Estimate::MayBeSlow
+    }
+}
+
+// A candidate `value` that can be removed from `cells`, because of the pattern formed by `pattern`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Elimination {
+    pub value: i8,
+    pub cells: Vec<(usize, usize)>,
+    pub pattern: Vec<(usize, usize)>,
+}
+
+/*
+    Pointing pairs (and triples): when a value's spots within a block all lie on one row or column,
+    it must go there, so it can be removed from the rest of that row/column outside the block.
+    Only eliminations that actually remove something are returned.
+*/
+pub fn find_pointing_pairs(matrix: &[Vec<i8>]) -> Vec<Elimination> {
+
+    let board = Board::from_matrix(matrix);
+    let size = board.size();
+    let sub_size = size.isqrt();
+
+    let candidates: Vec<Vec<i8>> = (0..size * size).map(|i| board.candidates((i / size, i % size))).collect();
+    let has = |(r, c): (usize, usize), value: i8| board.get(r, c) == 0 && candidates[r * size + c].contains(&value);
+
+    let mut eliminations: Vec<Elimination> = Vec::new();
+
+    for br in 0..sub_size {
+        for bc in 0..sub_size {
+            let in_block = |(r, c): (usize, usize)| r / sub_size == br && c / sub_size == bc;
+            let block: Vec<(usize, usize)> = (0..size).map(|i| (br * sub_size + i / sub_size, bc * sub_size + i % sub_size)).collect();
+
+            for value in 1..=size as i8 {
+                let spots: Vec<(usize, usize)> = block.iter().copied().filter(|&pos| has(pos, value)).collect();
+                if spots.len() < 2 {continue}
+
+                let mut lines: Vec<Vec<(usize, usize)>> = Vec::new();
+                if spots.iter().all(|&(r, _)| r == spots[0].0) {lines.push((0..size).map(|c| (spots[0].0, c)).collect())}
+                if spots.iter().all(|&(_, c)| c == spots[0].1) {lines.push((0..size).map(|r| (r, spots[0].1)).collect())}
+
+                for line in lines {
+                    let cells: Vec<(usize, usize)> = line.into_iter().filter(|&pos| !in_block(pos) && has(pos, value)).collect();
+
+                    if !cells.is_empty() {
+                        eliminations.push(Elimination {value, cells, pattern: spots.clone()});
+                    }
+                }
+            }
+        }
+    }
+
+    eliminations
+}
+
+/*
+    X-Wing: when a value has exactly two spots in each of two rows, and they are in the same two columns,
+    it takes two opposite corners of that rectangle, so it can be removed from the rest of both columns.
+    The same holds with rows and columns swapped. Only eliminations that actually remove something are returned.
+*/
+pub fn find_x_wing(matrix: &[Vec<i8>]) -> Vec<Elimination> {
+    let board = Board::from_matrix(matrix);
+    let size = board.size();
+
+    x_wing_eliminations(&(0..size * size).map(|i| board.candidates((i / size, i % size))).collect::<Vec<_>>(), size)
+}
+
+// find_x_wing on a candidate grid (row by row, empty for filled cells), which may already have eliminations applied.
+fn x_wing_eliminations(candidates: &[Vec<i8>], size: usize) -> Vec<Elimination> {
+
+    let has = |(r, c): (usize, usize), value: i8| candidates[r * size + c].contains(&value);
+
+    let mut eliminations: Vec<Elimination> = Vec::new();
+
+    // (line, index) -> cell, for rows as base lines and then for columns
+    type CellAt = fn(usize, usize) -> (usize, usize);
+    let orientations: [CellAt; 2] = [|line, i| (line, i), |line, i| (i, line)];
+
+    for cell_at in orientations {
+        for value in 1..=size as i8 {
+            // Base lines where the value has exactly two spots, with the indices of those spots
+            let bases: Vec<(usize, Vec<usize>)> = (0..size)
+                .map(|line| (line, (0..size).filter(|&i| has(cell_at(line, i), value)).collect::<Vec<usize>>()))
+                .filter(|(_, spots)| spots.len() == 2)
+                .collect();
+
+            for (k, (line_a, spots_a)) in bases.iter().enumerate() {
+                for (line_b, spots_b) in &bases[k + 1..] {
+                    if spots_a != spots_b {continue}
+
+                    let cells: Vec<(usize, usize)> = (0..size)
+                        .filter(|line| line != line_a && line != line_b)
+                        .flat_map(|line| spots_a.iter().map(move |&i| cell_at(line, i)))
+                        .filter(|&pos| has(pos, value))
+                        .collect();
+
+                    if !cells.is_empty() {
+                        let pattern = [*line_a, *line_b].iter().flat_map(|&line| spots_a.iter().map(move |&i| cell_at(line, i))).collect();
+                        eliminations.push(Elimination {value, cells, pattern});
+                    }
+                }
+            }
+        }
+    }
+
+    eliminations
+}
+
+/*
+    Swordfish: the X-Wing on three lines. When a value has two or three spots in each of three rows, all within
+    the same three columns, those rows place it once in each of the columns, so it can be removed from the rest of them.
+    The same holds with rows and columns swapped. Only eliminations that actually remove something are returned.
+*/
+pub fn find_swordfish(matrix: &[Vec<i8>]) -> Vec<Elimination> {
+    let board = Board::from_matrix(matrix);
+    let size = board.size();
+
+    swordfish_eliminations(&(0..size * size).map(|i| board.candidates((i / size, i % size))).collect::<Vec<_>>(), size)
+}
+
+// find_swordfish on a candidate grid, like x_wing_eliminations.
+fn swordfish_eliminations(candidates: &[Vec<i8>], size: usize) -> Vec<Elimination> {
+
+    let has = |(r, c): (usize, usize), value: i8| candidates[r * size + c].contains(&value);
+
+    let mut eliminations: Vec<Elimination> = Vec::new();
+
+    type CellAt = fn(usize, usize) -> (usize, usize);
+    let orientations: [CellAt; 2] = [|line, i| (line, i), |line, i| (i, line)];
+
+    for cell_at in orientations {
+        for value in 1..=size as i8 {
+            // Base lines where the value has two or three spots (one spot is a hidden single, not a fish)
+            let bases: Vec<(usize, Vec<usize>)> = (0..size)
+                .map(|line| (line, (0..size).filter(|&i| has(cell_at(line, i), value)).collect::<Vec<usize>>()))
+                .filter(|(_, spots)| (2..=3).contains(&spots.len()))
+                .collect();
+
+            for (a, (line_a, spots_a)) in bases.iter().enumerate() {
+                for (b, (line_b, spots_b)) in bases.iter().enumerate().skip(a + 1) {
+                    for (line_c, spots_c) in &bases[b + 1..] {
+                        let mut covered: Vec<usize> = [spots_a, spots_b, spots_c].into_iter().flatten().copied().collect();
+                        covered.sort_unstable();
+                        covered.dedup();
+
+                        if covered.len() != 3 {continue}
+
+                        let lines = [*line_a, *line_b, *line_c];
+
+                        let cells: Vec<(usize, usize)> = (0..size)
+                            .filter(|line| !lines.contains(line))
+                            .flat_map(|line| covered.iter().map(move |&i| cell_at(line, i)))
+                            .filter(|&pos| has(pos, value))
+                            .collect();
+
+                        if !cells.is_empty() {
+                            let pattern = [(line_a, spots_a), (line_b, spots_b), (line_c, spots_c)].into_iter()
+                                .flat_map(|(&line, spots)| spots.iter().map(move |&i| cell_at(line, i)))
+                                .collect();
+                            eliminations.push(Elimination {value, cells, pattern});
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    eliminations
+}
+
+/*
+    Empty cells that are the last one of their row, column or block: the value is the one digit that unit is missing.
+    Each cell is listed once, even when it completes several units. Units missing more than one digit
+    (i.e. holding duplicates) are skipped, they have no forced value.
+*/
+pub fn last_in_unit_cells(matrix: &[Vec<i8>]) -> Vec<(usize, usize, i8)> {
+
+    let size = matrix.len();
+    let mut cells: Vec<(usize, usize, i8)> = Vec::new();
+
+    for unit in units(size) {
+        let mut empty = unit.iter().filter(|&&(r, c)| matrix[r][c] == 0);
+        let (Some(&(row, col)), None) = (empty.next(), empty.next()) else {continue};
+
+        let mut missing = (1..=size as i8).filter(|value| unit.iter().all(|&(r, c)| matrix[r][c] != *value));
+
+        if let (Some(value), None) = (missing.next(), missing.next())
+            && !cells.iter().any(|&(r, c, _)| (r, c) == (row, col)) {
+            cells.push((row, col, value));
+        }
+    }
+
+    cells
+}
+
+/*
+    Candidates of the empty cell at `pos` that have no other spot in its row, column or block (hidden singles there).
+    Blocks and candidates follow `constraints`, so jigsaw regions and variant rules are taken into account.
+*/
+pub fn hidden_singles_at(matrix: &[Vec<i8>], pos: (usize, usize), constraints: &ConstraintSet) -> Vec<i8> {
+
+    let board = Board::from_matrix(matrix);
+    let size = board.size();
+    if board.get(pos.0, pos.1) != 0 {return Vec::new()}
+
+    // Rows and columns, then the blocks of `constraints` in place of the classic boxes
+    let cell_units: Vec<Vec<(usize, usize)>> = units(size).into_iter().take(2 * size)
+        .chain(constraints.blocks(size))
+        .filter(|unit| unit.contains(&pos))
+        .collect();
+
+    (1..=size as i8)
+        .filter(|&value| constraints.permits(&board, value, pos))
+        .filter(|&value| cell_units.iter().any(|unit| {
+            unit.iter().all(|&other| other == pos || board.get(other.0, other.1) != 0 || !constraints.permits(&board, value, other))
+        }))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sudoku::{Regions, is_complete_solution, parse_puzzle};
+
+    fn replay(puzzle: &[Vec<i8>], steps: &[SolveStep]) -> Vec<Vec<i8>> {
+        let mut matrix = puzzle.to_vec();
+        for step in steps {
+            matrix[step.pos.0][step.pos.1] = step.value;
+        }
+        matrix
+    }
+
+    #[test]
+    fn fish_are_used_before_guessing() {
+        let x_wing = parse_puzzle("000208000003090850069400002601000080052000600090050000000040200100800700000006400").unwrap();
+        let swordfish = parse_puzzle("060010200209007000000003060400000870900070054800002010000005400001000506003800000").unwrap();
+
+        for (puzzle, technique) in [(x_wing, Technique::XWing), (swordfish, Technique::Swordfish)] {
+            let steps = solve_with_steps(&puzzle).unwrap();
+
+            assert_eq!(hardest_technique(&puzzle), Some(technique));
+            assert!(is_complete_solution(&replay(&puzzle, &steps)));
+        }
+    }
+
+    #[test]
+    fn steps_rebuild_the_solution_with_the_simplest_technique() {
+        let puzzle = parse_puzzle("530070000600195000098000060800060003400803001700020006060000280000419005000080079").unwrap();
+        let steps = solve_with_steps(&puzzle).unwrap();
+
+        assert_eq!(steps.len(), puzzle.iter().flatten().filter(|&&v| v == 0).count());
+        assert!(is_complete_solution(&replay(&puzzle, &steps)));
+
+        // Hidden singles only when no cell is down to one candidate
+        for (done, step) in steps.iter().enumerate() {
+            let matrix = replay(&puzzle, &steps[..done]);
+            let naked = compute_candidates(&matrix).iter().enumerate()
+                .any(|(row, cells)| cells.iter().enumerate().any(|(col, cands)| matrix[row][col] == 0 && cands.len() == 1));
+
+            assert_eq!(step.technique == Technique::NakedSingle, naked);
+        }
+
+        // Nothing is forced on an empty board
+        assert_eq!(solve_with_steps(&vec![vec![0; 4]; 4]).unwrap()[0].technique, Technique::Guess);
+    }
+
+    #[test]
+    fn naked_pairs_are_found_once() {
+        // 1 and 2 are the only values left for the blanks of rows 0 and 1; each pair shares a row and a block
+        let matrix = parse_puzzle("0034340000000000").unwrap();
+
+        assert_eq!(find_naked_pairs(&matrix), [
+            NakedPair {cells: [(0, 0), (0, 1)], values: [1, 2]},
+            NakedPair {cells: [(1, 2), (1, 3)], values: [1, 2]},
+        ]);
+        assert!(find_naked_pairs(&vec![vec![0; 4]; 4]).is_empty());
+    }
+
+    #[test]
+    fn the_hidden_single_of_a_cell_is_flagged() {
+        // Every other cell of row 0 sees a 1, while (0, 0) still has all nine candidates
+        let mut matrix = vec![vec![0; 9]; 9];
+        for (row, col) in [(3, 1), (6, 2), (1, 3), (2, 6)] {
+            matrix[row][col] = 1;
+        }
+
+        assert_eq!(compute_candidates(&matrix)[0][0].len(), 9);
+        assert_eq!(hidden_singles_at(&matrix, (0, 0), &ConstraintSet::default()), [1]);
+        assert!(hidden_singles_at(&matrix, (0, 4), &ConstraintSet::default()).is_empty());
+        assert!(hidden_singles_at(&matrix, (3, 1), &ConstraintSet::default()).is_empty());
+
+        // (1, 0) sits in the classic box of the 2 at (0, 1), but not in its jigsaw region
+        let jigsaw = ConstraintSet {regions: Some(Regions::from_map("AAAB CABB CCDB CDDD").unwrap()), ..Default::default()};
+        let matrix = parse_puzzle("0200000000020000").unwrap();
+
+        assert_eq!(hidden_singles_at(&matrix, (1, 0), &jigsaw), [2]);
+        assert!(hidden_singles_at(&matrix, (1, 0), &ConstraintSet::default()).is_empty());
+    }
+
+    #[test]
+    fn pointing_pairs_clear_the_rest_of_the_line() {
+        // The top-left block only has (0, 0) and (0, 1) left, so its 1 and 9 are both in row 0
+        let mut matrix = vec![vec![0; 9]; 9];
+        matrix[0][2] = 8;
+        matrix[1][..3].copy_from_slice(&[2, 3, 4]);
+        matrix[2][..3].copy_from_slice(&[5, 6, 7]);
+
+        let eliminations = find_pointing_pairs(&matrix);
+        let row: Vec<(usize, usize)> = (3..9).map(|col| (0, col)).collect();
+
+        for value in [1, 9] {
+            assert!(eliminations.contains(&Elimination {value, cells: row.clone(), pattern: vec![(0, 0), (0, 1)]}));
+        }
+        assert!(find_pointing_pairs(&vec![vec![0; 9]; 9]).is_empty());
+    }
+
+    #[test]
+    fn an_x_wing_clears_its_columns() {
+        // Rows 0 and 4 are full but for columns 0 and 4, so their 1 (and 9) can only go there
+        let mut matrix = vec![vec![0; 9]; 9];
+        for (col, (top, middle)) in [1, 2, 3, 5, 6, 7, 8].into_iter().zip([2, 3, 4, 5, 6, 7, 8].into_iter().zip([5, 6, 7, 8, 2, 3, 4])) {
+            matrix[0][col] = top;
+            matrix[4][col] = middle;
+        }
+
+        let cells: Vec<(usize, usize)> = [1, 2, 3, 5, 6, 7, 8].into_iter().flat_map(|row| [(row, 0), (row, 4)]).collect();
+        let eliminations = find_x_wing(&matrix);
+
+        for value in [1, 9] {
+            assert!(eliminations.contains(&Elimination {value, cells: cells.clone(), pattern: vec![(0, 0), (0, 4), (4, 0), (4, 4)]}));
+        }
+        assert!(find_x_wing(&vec![vec![0; 9]; 9]).is_empty());
+    }
+
+    #[test]
+    fn estimates_tell_easy_from_hard() {
+        let easy = parse_puzzle("530070000600195000098000060800060003400803001700020006060000280000419005000080079").unwrap();
+        let hard = parse_puzzle("000000000000003085001020000000507000004000100090000000500000073002010000000040009").unwrap();
+
+        assert_eq!(estimate_difficulty_fast(&easy), Estimate::LikelyFast);
+        assert_eq!(estimate_difficulty_fast(&hard), Estimate::MayBeSlow);
+        assert_eq!(estimate_difficulty_fast(&parse_puzzle("1100000000000000").unwrap()), Estimate::MayBeSlow);
+    }
+
+    #[test]
+    fn a_swordfish_clears_its_columns() {
+        // Rows 0, 3 and 6 are full but for columns 0, 3 and 6, which are left for their 1, 8 and 9
+        let mut matrix = vec![vec![0; 9]; 9];
+        let fills = [(0, [2, 3, 4, 5, 6, 7]), (3, [4, 5, 6, 7, 2, 3]), (6, [6, 7, 2, 3, 4, 5])];
+        for (row, values) in fills {
+            for (col, value) in [1, 2, 4, 5, 7, 8].into_iter().zip(values) {
+                matrix[row][col] = value;
+            }
+        }
+
+        let lines = [0, 3, 6];
+        let cells: Vec<(usize, usize)> = (0..9).filter(|row| !lines.contains(row)).flat_map(|row| lines.map(|col| (row, col))).collect();
+        let pattern: Vec<(usize, usize)> = lines.into_iter().flat_map(|row| lines.map(|col| (row, col))).collect();
+
+        let eliminations = find_swordfish(&matrix);
+        for value in [1, 8, 9] {
+            assert!(eliminations.contains(&Elimination {value, cells: cells.clone(), pattern: pattern.clone()}));
+        }
+        assert!(find_x_wing(&matrix).iter().all(|elimination| ![1, 8, 9].contains(&elimination.value)));
+    }
+
+    #[test]
+    fn the_last_empty_cell_of_a_row_gets_its_digit() {
+        let mut matrix = vec![vec![0; 9]; 9];
+        matrix[4] = vec![3, 1, 4, 0, 5, 9, 2, 6, 8];
+
+        assert_eq!(last_in_unit_cells(&matrix), [(4, 3, 7)]);
+        assert!(last_in_unit_cells(&vec![vec![0; 9]; 9]).is_empty());
+
+        // A duplicate leaves two digits missing: nothing is forced
+        matrix[4][0] = 1;
+        assert!(last_in_unit_cells(&matrix).is_empty());
+    }
+}
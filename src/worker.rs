@@ -0,0 +1,107 @@
+use std::{panic::{self, AssertUnwindSafe}, sync::{Arc, atomic::{AtomicBool, Ordering}, mpsc::{self, Sender}}, thread};
+
+type Job = Box<dyn FnOnce(&AtomicBool) + Send>;
+
+/*
+    A single background thread running the GUI's computations in submission order, one at a time,
+    instead of a new thread per button press. Results still come back through each job's own channel.
+*/
+pub struct Worker {
+    jobs: Sender<(Job, Arc<AtomicBool>)>,
+    flags: Vec<Arc<AtomicBool>>, // Flags of the jobs still queued or running
+}
+
+impl Worker {
+    pub fn new() -> Self {
+        let (jobs, queue) = mpsc::channel::<(Job, Arc<AtomicBool>)>();
+
+        // Ends once the Worker (the only sender) is dropped and the queue is drained.
+        thread::spawn(move || {
+            for (job, cancel) in queue {
+                /*
+                    A panicking job is reported by the default hook; the worker moves on to the next one.
+                    Its senders are dropped on unwinding, so the GUI sees a disconnected channel instead of waiting forever.
+                */
+                let _ = panic::catch_unwind(AssertUnwindSafe(|| job(&cancel)));
+            }
+        });
+
+        Self {jobs, flags: Vec::new()}
+    }
+
+    // Queues `job`, which gets a flag raised by cancel() until it finishes.
+    pub fn submit(&mut self, job: impl FnOnce(&AtomicBool) + Send + 'static) {
+        // A flag only the Worker still holds belongs to a finished job.
+        self.flags.retain(|flag| Arc::strong_count(flag) > 1);
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.flags.push(Arc::clone(&cancel));
+        self.jobs.send((Box::new(job), cancel)).expect("Worker thread stopped");
+    }
+
+    // Asks every queued or running job to stop. Jobs that never look at their flag just run to the end.
+    pub fn cancel(&self) {
+        for flag in &self.flags {
+            flag.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn two_jobs_run_without_spawning_unbounded_threads() {
+        let mut worker = Worker::new();
+        let (tx, rx) = mpsc::channel();
+
+        for _ in 0..2 {
+            let tx = tx.clone();
+            worker.submit(move |_| tx.send(thread::current().id()).unwrap());
+        }
+
+        let (first, second) = (rx.recv().unwrap(), rx.recv().unwrap());
+        assert_eq!(first, second);
+        assert_ne!(first, thread::current().id());
+    }
+
+    #[test]
+    fn cancel_reaches_every_queued_job() {
+        let mut worker = Worker::new();
+        let (release, blocked) = mpsc::channel::<()>();
+        let (tx, rx) = mpsc::channel();
+
+        // The first job holds the worker until the second one is queued and both are cancelled.
+        let first = tx.clone();
+        worker.submit(move |cancel| {
+            blocked.recv().unwrap();
+            first.send(cancel.load(Ordering::Relaxed)).unwrap();
+        });
+        worker.submit(move |cancel| tx.send(cancel.load(Ordering::Relaxed)).unwrap());
+
+        worker.cancel();
+        release.send(()).unwrap();
+
+        assert!(rx.recv().unwrap());
+        assert!(rx.recv().unwrap());
+    }
+
+    #[test]
+    fn panicking_job_disconnects_its_channel() {
+        let mut worker = Worker::new();
+        let (tx, rx) = mpsc::channel::<()>();
+
+        worker.submit(move |_| {
+            let _tx = tx;
+            panic!("job failed");
+        });
+
+        assert!(rx.recv().is_err());
+
+        // The worker survives and runs the next job.
+        let (tx, rx) = mpsc::channel();
+        worker.submit(move |_| tx.send(()).unwrap());
+        assert!(rx.recv().is_ok());
+    }
+}